@@ -0,0 +1,99 @@
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+/// An external program, used by `command` IP sources and hooks
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(from = "CommandForm")]
+pub struct CommandSpec {
+    /// Program and its arguments
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<u64>,
+}
+
+/// A command is a program path, a bare argument list or a table with options
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged, deny_unknown_fields)]
+enum CommandForm {
+    Program(String),
+    Args(Vec<String>),
+    Full {
+        args: Vec<String>,
+        #[serde(default)]
+        timeout_seconds: Option<u64>,
+    },
+}
+
+impl From<CommandForm> for CommandSpec {
+    fn from(form: CommandForm) -> Self {
+        match form {
+            CommandForm::Program(program) => Self {
+                args: vec![program],
+                timeout_seconds: None,
+            },
+            CommandForm::Args(args) => Self {
+                args,
+                timeout_seconds: None,
+            },
+            CommandForm::Full {
+                args,
+                timeout_seconds,
+            } => Self {
+                args,
+                timeout_seconds,
+            },
+        }
+    }
+}
+
+impl CommandSpec {
+    /// Run the program with `envs` added to its environment and return its
+    /// standard output. It is killed after `timeout_seconds`, or
+    /// `default_timeout` if unset. `label` names the command in errors.
+    pub async fn run_with_timeout(
+        &self,
+        label: &str,
+        default_timeout: Duration,
+        envs: Vec<(String, String)>,
+    ) -> Result<String> {
+        let (program, args) = self
+            .args
+            .split_first()
+            .ok_or_else(|| anyhow!("{} has no program", label))?;
+
+        let timeout = self
+            .timeout_seconds
+            .map(Duration::from_secs)
+            .unwrap_or(default_timeout);
+
+        let output = Command::new(program)
+            .args(args)
+            .envs(envs)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output();
+
+        let output = tokio::time::timeout(timeout, output)
+            .await
+            .map_err(|_| anyhow!("{} {} timed out after {:?}", label, program, timeout))?
+            .with_context(|| format!("Failed to run {}: {}", label.to_lowercase(), program))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow!(
+                "{} {} failed ({}): {}",
+                label,
+                program,
+                output.status,
+                stderr.trim()
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
@@ -0,0 +1,195 @@
+use toml_edit::{Item, Table, Value};
+
+/// Prefix of override variables, e.g. `IPDNSD_DAEMON__INTERVAL_SECONDS`
+pub const ENV_PREFIX: &str = "IPDNSD_";
+
+/// Separator between key segments
+const SEPARATOR: &str = "__";
+
+/// Variables below this prefix define one extra DNS entry
+const ENTRY_PREFIX: &str = "entry";
+
+/// Setting overrides from the environment, sorted by name.
+///
+/// Only variables with a `__` separator count, so `IPDNSD_CONFIG` and the
+/// variables set for hooks are not overrides.
+pub fn env_overrides() -> Vec<(String, String)> {
+    let mut overrides: Vec<(String, String)> = std::env::vars()
+        .filter(|(name, _)| {
+            name.strip_prefix(ENV_PREFIX)
+                .is_some_and(|rest| rest.contains(SEPARATOR))
+        })
+        .collect();
+    overrides.sort();
+    overrides
+}
+
+/// Apply overrides to a parsed configuration, returning a message for each
+/// one that can't be applied
+pub(super) fn apply_overrides(root: &mut Table, overrides: &[(String, String)]) -> Vec<String> {
+    let mut errors = Vec::new();
+    let mut entry = Item::None;
+
+    for (name, raw) in overrides {
+        let path: Vec<String> = name[ENV_PREFIX.len()..]
+            .split(SEPARATOR)
+            .map(str::to_lowercase)
+            .collect();
+        if path.iter().any(String::is_empty) {
+            errors.push(format!("{}: empty key segment", name));
+            continue;
+        }
+
+        let result = match path.split_first() {
+            Some((first, rest)) if first == ENTRY_PREFIX => set_path(&mut entry, rest, parse_value(raw)),
+            Some((first, rest)) => set_path(
+                root.entry(first).or_insert(Item::None),
+                rest,
+                parse_value(raw),
+            ),
+            None => Ok(()),
+        };
+        if let Err(e) = result {
+            errors.push(format!("{}: {}", name, e));
+        }
+    }
+
+    if let Item::Table(entry) = entry {
+        let entries = root.entry("dns_entries").or_insert(Item::None);
+        normalize(entries);
+        match entries {
+            Item::None => *entries = Item::ArrayOfTables([entry].into_iter().collect()),
+            Item::ArrayOfTables(tables) => tables.push(entry),
+            _ => errors.push(format!(
+                "{}{}{}*: dns_entries is not an array of tables",
+                ENV_PREFIX,
+                ENTRY_PREFIX.to_uppercase(),
+                SEPARATOR
+            )),
+        }
+    }
+
+    errors
+}
+
+/// Values are TOML (`300`, `true`, `["a", "b"]`, `{ interface = "eth0" }`);
+/// anything else is taken as a plain string
+fn parse_value(raw: &str) -> Value {
+    raw.parse::<Value>()
+        .map(|value| value.decorated("", ""))
+        .unwrap_or_else(|_| Value::from(raw))
+}
+
+/// Set `path` below `item`, creating tables as needed. Numeric segments
+/// index into arrays of tables, e.g. `DNS_ENTRIES__0__TTL`.
+fn set_path(item: &mut Item, path: &[String], value: Value) -> Result<(), String> {
+    let Some((key, rest)) = path.split_first() else {
+        *item = Item::Value(value);
+        return Ok(());
+    };
+
+    normalize(item);
+    match item {
+        Item::None => {
+            *item = Item::Table(Table::new());
+            set_path(item, path, value)
+        }
+        Item::Table(table) => set_path(table.entry(key).or_insert(Item::None), rest, value),
+        Item::ArrayOfTables(tables) => {
+            let len = tables.len();
+            let table = key
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| tables.get_mut(index))
+                .ok_or_else(|| format!("`{}` is not an index below {}", key, len))?;
+            match rest.split_first() {
+                Some((field, rest)) => {
+                    set_path(table.entry(field).or_insert(Item::None), rest, value)
+                }
+                None => Err("can't replace a whole table".to_string()),
+            }
+        }
+        Item::Value(_) => Err(format!("can't set `{}` on a value that isn't a table", key)),
+    }
+}
+
+/// Turn inline tables and arrays of inline tables into their table forms
+fn normalize(item: &mut Item) {
+    let converted = match std::mem::take(item) {
+        Item::Value(Value::InlineTable(table)) => Item::Table(table.into_table()),
+        Item::Value(Value::Array(array)) if array.iter().all(Value::is_inline_table) => {
+            Item::Value(Value::Array(array))
+                .into_array_of_tables()
+                .map(Item::ArrayOfTables)
+                .unwrap_or_else(|item| item)
+        }
+        other => other,
+    };
+    *item = converted;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ExternalIpService, Settings};
+    use toml_edit::DocumentMut;
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let mut doc: DocumentMut = r#"
+[daemon]
+interval_seconds = 300
+
+[[dns_entries]]
+provider = "godaddy"
+domain = "example.com"
+record_name = "@"
+record_type = "A"
+ip_source = "external"
+"#
+        .parse()
+        .unwrap();
+
+        let errors = apply_overrides(
+            doc.as_table_mut(),
+            &vars(&[
+                ("IPDNSD_DAEMON__INTERVAL_SECONDS", "60"),
+                ("IPDNSD_DAEMON__DAMPING__STABLE_CHECKS", "2"),
+                ("IPDNSD_DNS_ENTRIES__0__TTL", "3600"),
+                ("IPDNSD_DNS_ENTRIES__3__TTL", "3600"),
+                (
+                    "IPDNSD_EXTERNAL_IP__SERVICES",
+                    r#"[{ url = "https://ip.example.com" }]"#,
+                ),
+                ("IPDNSD_ENTRY__PROVIDER", "godaddy"),
+                ("IPDNSD_ENTRY__DOMAIN", "example.org"),
+                ("IPDNSD_ENTRY__RECORD_NAME", "vpn"),
+                ("IPDNSD_ENTRY__RECORD_TYPE", "A"),
+                ("IPDNSD_ENTRY__IP_SOURCE", "internal"),
+            ]),
+        );
+
+        assert_eq!(
+            errors,
+            vec!["IPDNSD_DNS_ENTRIES__3__TTL: `3` is not an index below 1".to_string()]
+        );
+        assert_eq!(doc["daemon"]["interval_seconds"].as_integer(), Some(60));
+        assert_eq!(doc["daemon"]["damping"]["stable_checks"].as_integer(), Some(2));
+        assert_eq!(doc["dns_entries"][0]["ttl"].as_integer(), Some(3600));
+        assert_eq!(doc["dns_entries"][1]["domain"].as_str(), Some("example.org"));
+        assert_eq!(doc["dns_entries"][1]["ip_source"].as_str(), Some("internal"));
+
+        let settings: Settings = toml::from_str(&doc.to_string()).unwrap();
+        assert_eq!(
+            settings.external_ip.services,
+            [ExternalIpService::new("https://ip.example.com")]
+        );
+        assert_eq!(settings.dns_entries.len(), 2);
+    }
+}
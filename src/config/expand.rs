@@ -0,0 +1,118 @@
+use std::ops::Range;
+
+use toml_edit::Item;
+
+/// List keys of the compact entry syntax and the key each value becomes
+const LIST_KEYS: &[(&str, &str)] = &[
+    ("domains", "domain"),
+    ("record_names", "record_name"),
+    ("record_types", "record_type"),
+];
+
+/// Expand a `dns_entries` element using `domains`, `record_names` or
+/// `record_types` into one element per combination, in that order.
+///
+/// Expanded elements keep the spans of the original, so problems in them are
+/// reported at the right place.
+pub(super) fn expand_entry(item: &Item) -> Result<Vec<Item>, (Option<Range<usize>>, String)> {
+    let Some(table) = item.as_table_like() else {
+        return Ok(vec![item.clone()]);
+    };
+
+    let mut expanded = vec![item.clone()];
+    for (list, single) in LIST_KEYS {
+        let Some(values) = table.get(list) else {
+            continue;
+        };
+        if table.contains_key(single) {
+            return Err((
+                values.span(),
+                format!("set either `{}` or `{}`, not both", single, list),
+            ));
+        }
+        let values = match values.as_array() {
+            Some(array) if !array.is_empty() && array.iter().all(|v| v.is_str()) => array,
+            _ => {
+                return Err((
+                    values.span(),
+                    format!("`{}` must be a non-empty array of strings", list),
+                ))
+            }
+        };
+
+        expanded = expanded
+            .into_iter()
+            .flat_map(|entry| {
+                values.iter().map(move |value| {
+                    let mut entry = entry.clone();
+                    if let Some(table) = entry.as_table_like_mut() {
+                        table.remove(list);
+                        table.insert(single, Item::Value(value.clone()));
+                    }
+                    entry
+                })
+            })
+            .collect();
+    }
+
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toml_edit::DocumentMut;
+
+    fn entries(source: &str) -> Result<Vec<String>, String> {
+        let doc: DocumentMut = source.parse().unwrap();
+        let items = expand_entry(&doc["dns_entries"][0]).map_err(|(_, message)| message)?;
+
+        Ok(items
+            .iter()
+            .map(|item| {
+                ["domain", "record_name", "record_type"]
+                    .map(|key| item[key].as_str().unwrap_or_default().to_string())
+                    .join(" ")
+            })
+            .collect())
+    }
+
+    #[test]
+    fn test_expand_entry() {
+        assert_eq!(
+            entries(
+                r#"
+[[dns_entries]]
+domains = ["example.com", "example.org"]
+record_names = ["@", "www"]
+record_types = ["A", "AAAA"]
+"#
+            )
+            .unwrap(),
+            [
+                "example.com @ A",
+                "example.com @ AAAA",
+                "example.com www A",
+                "example.com www AAAA",
+                "example.org @ A",
+                "example.org @ AAAA",
+                "example.org www A",
+                "example.org www AAAA",
+            ]
+        );
+
+        assert_eq!(
+            entries("[[dns_entries]]\ndomain = \"example.com\"\nrecord_name = \"@\"\nrecord_type = \"A\"\n")
+                .unwrap(),
+            ["example.com @ A"]
+        );
+        assert_eq!(
+            entries("[[dns_entries]]\nrecord_name = \"@\"\nrecord_names = [\"www\"]\n").unwrap_err(),
+            "set either `record_name` or `record_names`, not both"
+        );
+        assert_eq!(
+            entries("[[dns_entries]]\nrecord_types = []\n").unwrap_err(),
+            "`record_types` must be a non-empty array of strings"
+        );
+    }
+}
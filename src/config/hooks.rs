@@ -0,0 +1,51 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::command::CommandSpec;
+
+/// Executables run after updates, globally or for a single entry, with
+/// details about the update in their environment
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct HooksConfig {
+    /// Run after a record was updated successfully
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_change: Option<CommandSpec>,
+    /// Run after an update failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_failure: Option<CommandSpec>,
+}
+
+impl HooksConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hooks() {
+        let hooks: HooksConfig = toml::from_str(
+            r#"
+on_change = "/etc/ipdnsd/hooks/reload-firewall"
+on_failure = { args = ["logger", "-t", "ipdnsd", "update failed"], timeout_seconds = 5 }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            hooks.on_change.unwrap().args,
+            vec!["/etc/ipdnsd/hooks/reload-firewall"]
+        );
+        let on_failure = hooks.on_failure.unwrap();
+        assert_eq!(on_failure.args.len(), 4);
+        assert_eq!(on_failure.timeout_seconds, Some(5));
+
+        let hooks: HooksConfig =
+            toml::from_str(r#"on_change = ["systemctl", "restart", "wg-quick@wg0"]"#).unwrap();
+        assert_eq!(hooks.on_change.unwrap().args[0], "systemctl");
+    }
+}
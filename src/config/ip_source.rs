@@ -0,0 +1,494 @@
+use std::fmt;
+use std::net::{IpAddr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+
+use ipnet::IpNet;
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::command::CommandSpec;
+use super::settings::AddressFamily;
+
+/// Where the address for a DNS entry comes from.
+///
+/// Simple sources are written as a string (`ip_source = "external"`), sources
+/// with options as a table (`ip_source = { internal = { interface = "eth0" } }`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum IpSource {
+    External,
+    Internal(InternalSelector),
+    Command(CommandSpec),
+    Prefix(PrefixSource),
+    /// A fixed address
+    Static(IpAddr),
+    /// A file containing the address, re-read whenever it changes. Relative
+    /// paths are relative to the configuration file.
+    File(PathBuf),
+    /// An address from the cloud instance metadata service
+    Metadata(MetadataSource),
+    /// The first healthy target of an ordered list
+    Failover(Vec<FailoverTarget>),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct InternalSelector {
+    /// Only consider addresses on this interface
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interface: Option<String>,
+    /// Only consider addresses inside this network (e.g. "192.168.10.0/24")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<String>")]
+    pub cidr: Option<IpNet>,
+    /// Interface name patterns to skip, `*` and `?` are wildcards (e.g. "docker*")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub family: AddressFamily,
+    /// Preferred scope when choosing between IPv6 addresses
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub ipv6_scope: Ipv6Scope,
+    /// Prefer temporary (privacy extension) IPv6 addresses over stable ones
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub prefer_temporary: bool,
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+impl InternalSelector {
+    /// Whether any selection option has been set
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Ipv6Scope {
+    #[default]
+    Global,
+    /// Unique local addresses (fc00::/7)
+    Ula,
+    LinkLocal,
+}
+
+/// IPv6 address built from the current delegated prefix and a fixed interface identifier
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PrefixSource {
+    /// Interface identifier placed after the prefix (e.g. "::10")
+    pub suffix: Ipv6Addr,
+    #[serde(default = "default_prefix_length")]
+    pub prefix_length: u8,
+    /// Where the current prefix is learned from
+    #[serde(default)]
+    pub from: PrefixOrigin,
+    /// Interface to take the prefix from when `from = "interface"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interface: Option<String>,
+}
+
+fn default_prefix_length() -> u8 {
+    64
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PrefixOrigin {
+    /// A global IPv6 address on a local interface
+    #[default]
+    Interface,
+    /// The IPv6 address seen by the external IP services
+    External,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MetadataSource {
+    pub provider: CloudProvider,
+    #[serde(default)]
+    pub address: MetadataAddress,
+    /// Override the metadata service base URL (e.g. "http://169.254.169.254")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CloudProvider {
+    Aws,
+    Gcp,
+    Azure,
+    OpenStack,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MetadataAddress {
+    #[default]
+    Public,
+    Private,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FailoverTarget {
+    pub ip_source: IpSource,
+    /// Targets without a health check are always considered healthy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health_check: Option<HealthCheck>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum HealthCheck {
+    /// Connect to a TCP port on the target address
+    Tcp {
+        port: u16,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+    /// GET a URL; `{ip}` is replaced with the target address
+    Http {
+        url: String,
+        /// Required status code; any 2xx status if unset
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expect_status: Option<u16>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+}
+
+/// Table form of the sources that take options
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum IpSourceTable {
+    Internal(InternalSelector),
+    Command(CommandSpec),
+    Prefix(PrefixSource),
+    Static(IpAddr),
+    File(PathBuf),
+    Metadata(MetadataSource),
+    Failover(Vec<FailoverTarget>),
+}
+
+impl From<IpSourceTable> for IpSource {
+    fn from(table: IpSourceTable) -> Self {
+        match table {
+            IpSourceTable::Internal(selector) => IpSource::Internal(selector),
+            IpSourceTable::Command(command) => IpSource::Command(command),
+            IpSourceTable::Prefix(prefix) => IpSource::Prefix(prefix),
+            IpSourceTable::Static(ip) => IpSource::Static(ip),
+            IpSourceTable::File(path) => IpSource::File(path),
+            IpSourceTable::Metadata(metadata) => IpSource::Metadata(metadata),
+            IpSourceTable::Failover(targets) => IpSource::Failover(targets),
+        }
+    }
+}
+
+impl IpSource {
+    /// Make relative `file` paths relative to `base`, the directory of the
+    /// configuration file the source is defined in
+    pub fn resolve_paths(&mut self, base: &Path) {
+        match self {
+            IpSource::File(path) if path.is_relative() => *path = base.join(&*path),
+            IpSource::Failover(targets) => {
+                for target in targets {
+                    target.ip_source.resolve_paths(base);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for IpSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpSource::External => write!(f, "external"),
+            IpSource::Internal(_) => write!(f, "internal"),
+            IpSource::Command(_) => write!(f, "command"),
+            IpSource::Prefix(_) => write!(f, "prefix"),
+            IpSource::Static(_) => write!(f, "static"),
+            IpSource::File(_) => write!(f, "file"),
+            IpSource::Metadata(_) => write!(f, "metadata"),
+            IpSource::Failover(_) => write!(f, "failover"),
+        }
+    }
+}
+
+impl Serialize for IpSource {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            IpSource::External => serializer.serialize_str("external"),
+            IpSource::Internal(selector) if selector.is_default() => {
+                serializer.serialize_str("internal")
+            }
+            IpSource::Internal(selector) => {
+                IpSourceTable::Internal(selector.clone()).serialize(serializer)
+            }
+            IpSource::Command(command) => {
+                IpSourceTable::Command(command.clone()).serialize(serializer)
+            }
+            IpSource::Prefix(prefix) => IpSourceTable::Prefix(prefix.clone()).serialize(serializer),
+            IpSource::Static(ip) => IpSourceTable::Static(*ip).serialize(serializer),
+            IpSource::File(path) => IpSourceTable::File(path.clone()).serialize(serializer),
+            IpSource::Metadata(metadata) => {
+                IpSourceTable::Metadata(metadata.clone()).serialize(serializer)
+            }
+            IpSource::Failover(targets) => {
+                IpSourceTable::Failover(targets.clone()).serialize(serializer)
+            }
+        }
+    }
+}
+
+impl JsonSchema for IpSource {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "IpSource".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "Where the address comes from: \"external\", \"internal\", a fixed IP address or a source table",
+            "anyOf": [
+                { "type": "string", "enum": ["external", "internal"] },
+                { "type": "string", "format": "ipv4" },
+                { "type": "string", "format": "ipv6" },
+                generator.subschema_for::<IpSourceTable>(),
+            ]
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for IpSource {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct IpSourceVisitor;
+
+        impl<'de> Visitor<'de> for IpSourceVisitor {
+            type Value = IpSource;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("\"external\", \"internal\", an IP address or a source table")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<IpSource, E> {
+                match value {
+                    "external" => Ok(IpSource::External),
+                    "internal" => Ok(IpSource::Internal(InternalSelector::default())),
+                    other => match other.parse() {
+                        // A bare address is shorthand for { static = "..." }
+                        Ok(ip) => Ok(IpSource::Static(ip)),
+                        Err(_) => Err(E::unknown_variant(other, &["external", "internal"])),
+                    },
+                }
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<IpSource, A::Error> {
+                IpSourceTable::deserialize(de::value::MapAccessDeserializer::new(map))
+                    .map(IpSource::from)
+            }
+        }
+
+        deserializer.deserialize_any(IpSourceVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize, Serialize)]
+    struct Wrapper {
+        ip_source: IpSource,
+    }
+
+    #[test]
+    fn test_parse_string_sources() {
+        let w: Wrapper = toml::from_str(r#"ip_source = "external""#).unwrap();
+        assert_eq!(w.ip_source, IpSource::External);
+
+        let w: Wrapper = toml::from_str(r#"ip_source = "internal""#).unwrap();
+        assert_eq!(w.ip_source, IpSource::Internal(InternalSelector::default()));
+
+        assert!(toml::from_str::<Wrapper>(r#"ip_source = "elsewhere""#).is_err());
+    }
+
+    #[test]
+    fn test_parse_internal_selector() {
+        let w: Wrapper = toml::from_str(
+            r#"ip_source = { internal = { cidr = "192.168.10.0/24", exclude = ["docker*", "veth*"], ipv6_scope = "ula" } }"#,
+        )
+        .unwrap();
+
+        let IpSource::Internal(selector) = w.ip_source else {
+            panic!("expected internal source");
+        };
+        assert_eq!(selector.cidr, Some("192.168.10.0/24".parse().unwrap()));
+        assert_eq!(selector.exclude, vec!["docker*", "veth*"]);
+        assert_eq!(selector.ipv6_scope, Ipv6Scope::Ula);
+        assert!(!selector.prefer_temporary);
+    }
+
+    #[test]
+    fn test_parse_command_source() {
+        let w: Wrapper =
+            toml::from_str(r#"ip_source = { command = ["/usr/local/bin/wan-ip", "--v4"] }"#)
+                .unwrap();
+        assert_eq!(
+            w.ip_source,
+            IpSource::Command(CommandSpec {
+                args: vec!["/usr/local/bin/wan-ip".to_string(), "--v4".to_string()],
+                timeout_seconds: None,
+            })
+        );
+
+        let w: Wrapper = toml::from_str(
+            r#"ip_source = { command = { args = ["fritz-wan-ip"], timeout_seconds = 5 } }"#,
+        )
+        .unwrap();
+        let IpSource::Command(command) = w.ip_source else {
+            panic!("expected command source");
+        };
+        assert_eq!(command.timeout_seconds, Some(5));
+
+        let w: Wrapper = toml::from_str(r#"ip_source = { command = "wan-ip" }"#).unwrap();
+        assert_eq!(
+            w.ip_source,
+            IpSource::Command(CommandSpec {
+                args: vec!["wan-ip".to_string()],
+                timeout_seconds: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_prefix_source() {
+        let w: Wrapper = toml::from_str(r#"ip_source = { prefix = { suffix = "::10" } }"#).unwrap();
+        assert_eq!(
+            w.ip_source,
+            IpSource::Prefix(PrefixSource {
+                suffix: "::10".parse().unwrap(),
+                prefix_length: 64,
+                from: PrefixOrigin::Interface,
+                interface: None,
+            })
+        );
+
+        let w: Wrapper = toml::from_str(
+            r#"ip_source = { prefix = { suffix = "::1:0:0:10", prefix_length = 56, from = "external" } }"#,
+        )
+        .unwrap();
+        let IpSource::Prefix(prefix) = w.ip_source else {
+            panic!("expected prefix source");
+        };
+        assert_eq!(prefix.prefix_length, 56);
+        assert_eq!(prefix.from, PrefixOrigin::External);
+    }
+
+    #[test]
+    fn test_parse_static_and_file_sources() {
+        let w: Wrapper = toml::from_str(r#"ip_source = { static = "203.0.113.10" }"#).unwrap();
+        assert_eq!(w.ip_source, IpSource::Static("203.0.113.10".parse().unwrap()));
+
+        let w: Wrapper = toml::from_str(r#"ip_source = { file = "/run/wan-ip" }"#).unwrap();
+        assert_eq!(w.ip_source, IpSource::File(PathBuf::from("/run/wan-ip")));
+
+        assert!(toml::from_str::<Wrapper>(r#"ip_source = { static = "not-an-ip" }"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_metadata_source() {
+        let w: Wrapper = toml::from_str(r#"ip_source = { metadata = { provider = "aws" } }"#)
+            .unwrap();
+        assert_eq!(
+            w.ip_source,
+            IpSource::Metadata(MetadataSource {
+                provider: CloudProvider::Aws,
+                address: MetadataAddress::Public,
+                endpoint: None,
+            })
+        );
+
+        let w: Wrapper = toml::from_str(
+            r#"ip_source = { metadata = { provider = "openstack", address = "private" } }"#,
+        )
+        .unwrap();
+        let IpSource::Metadata(metadata) = w.ip_source else {
+            panic!("expected metadata source");
+        };
+        assert_eq!(metadata.provider, CloudProvider::OpenStack);
+        assert_eq!(metadata.address, MetadataAddress::Private);
+    }
+
+    #[test]
+    fn test_parse_failover_source() {
+        let w: Wrapper = toml::from_str(
+            r#"
+[[ip_source.failover]]
+ip_source = "external"
+health_check = { type = "http", url = "http://{ip}/health", expect_status = 204 }
+
+[[ip_source.failover]]
+ip_source = "198.51.100.20"
+health_check = { type = "tcp", port = 443 }
+
+[[ip_source.failover]]
+ip_source = { static = "198.51.100.30" }
+"#,
+        )
+        .unwrap();
+
+        let IpSource::Failover(targets) = w.ip_source else {
+            panic!("expected failover source");
+        };
+        assert_eq!(targets.len(), 3);
+        assert_eq!(targets[0].ip_source, IpSource::External);
+        assert_eq!(
+            targets[0].health_check,
+            Some(HealthCheck::Http {
+                url: "http://{ip}/health".to_string(),
+                expect_status: Some(204),
+                timeout_seconds: None,
+            })
+        );
+        assert_eq!(targets[1].ip_source, IpSource::Static("198.51.100.20".parse().unwrap()));
+        assert_eq!(
+            targets[1].health_check,
+            Some(HealthCheck::Tcp {
+                port: 443,
+                timeout_seconds: None,
+            })
+        );
+        assert!(targets[2].health_check.is_none());
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let w = Wrapper {
+            ip_source: IpSource::Internal(InternalSelector::default()),
+        };
+        assert_eq!(toml::to_string(&w).unwrap().trim(), r#"ip_source = "internal""#);
+
+        let w = Wrapper {
+            ip_source: IpSource::Internal(InternalSelector {
+                interface: Some("eth0".to_string()),
+                ..Default::default()
+            }),
+        };
+        let parsed: Wrapper = toml::from_str(&toml::to_string(&w).unwrap()).unwrap();
+        assert_eq!(parsed.ip_source, w.ip_source);
+
+        let w = Wrapper {
+            ip_source: IpSource::Command(CommandSpec {
+                args: vec!["wan-ip".to_string()],
+                timeout_seconds: Some(3),
+            }),
+        };
+        let parsed: Wrapper = toml::from_str(&toml::to_string(&w).unwrap()).unwrap();
+        assert_eq!(parsed.ip_source, w.ip_source);
+    }
+}
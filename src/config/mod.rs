@@ -1,3 +1,32 @@
+mod command;
+mod env;
+mod expand;
+mod hooks;
+mod ip_source;
+mod notifications;
+mod schema;
 mod settings;
+mod validate;
 
-pub use settings::{DaemonConfig, DnsEntry, IpSource, Settings};
+pub use command::CommandSpec;
+pub use env::{env_overrides, ENV_PREFIX};
+pub use hooks::HooksConfig;
+pub use ip_source::{
+    CloudProvider, FailoverTarget, HealthCheck, InternalSelector, IpSource,
+    Ipv6Scope, MetadataAddress, MetadataSource, PrefixOrigin, PrefixSource,
+};
+pub use notifications::{
+    DiscordConfig, EmailConfig, EventKind, GotifyConfig, MatrixConfig, NotificationConfig,
+    NotificationTarget, NtfyConfig, SlackConfig, SmtpAuth, SmtpSecurity, TelegramConfig,
+    WebhookConfig,
+};
+pub use schema::config_schema;
+pub use settings::{
+    drop_in_files, AddressFamily, AddressPolicy, AddressScope, ConsensusConfig, CredentialBackend,
+    CredentialsConfig, DaemonConfig, DampingConfig, DnsEntry, ExternalIpConfig, ExternalIpService, HistoryConfig, ResponseFormat,
+    Settings, CONFIG_ENV,
+};
+pub use validate::{
+    validate, validate_sources, validate_with_overrides, ConfigError, ConfigSource, Diagnostic,
+    HasCredentials, MIN_INTERVAL_SECONDS,
+};
@@ -0,0 +1,294 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::de::{self, DeserializeOwned};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Something that happened while checking and updating DNS entries
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// The address of an entry changed and will be published
+    IpChanged,
+    /// A DNS record was updated
+    RecordUpdated,
+    /// Updating a DNS record failed
+    UpdateFailed,
+    /// The DNS provider rejected the credentials
+    AuthFailed,
+    /// An entry updated successfully after previous failures
+    Recovered,
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventKind::IpChanged => write!(f, "ip_changed"),
+            EventKind::RecordUpdated => write!(f, "record_updated"),
+            EventKind::UpdateFailed => write!(f, "update_failed"),
+            EventKind::AuthFailed => write!(f, "auth_failed"),
+            EventKind::Recovered => write!(f, "recovered"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct NotificationConfig {
+    /// Events sent to this target; all events if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<EventKind>,
+    /// Suppress repeats of an identical event within this many seconds
+    #[serde(default = "default_dedup_seconds")]
+    pub dedup_seconds: u64,
+    /// Maximum notifications sent to this target per rolling hour
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_per_hour: Option<u32>,
+    #[serde(flatten)]
+    pub target: NotificationTarget,
+}
+
+impl NotificationConfig {
+    pub fn new(target: NotificationTarget) -> Self {
+        Self {
+            events: Vec::new(),
+            dedup_seconds: default_dedup_seconds(),
+            max_per_hour: None,
+            target,
+        }
+    }
+
+    pub fn wants(&self, kind: EventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+}
+
+// `flatten` would silently accept unknown keys, so the common options are
+// split from the target's table by hand and the target rejects the rest
+impl<'de> Deserialize<'de> for NotificationConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        fn take<T: DeserializeOwned, E: de::Error>(
+            table: &mut toml::Table,
+            key: &str,
+        ) -> Result<Option<T>, E> {
+            table
+                .remove(key)
+                .map(|value| T::deserialize(value).map_err(E::custom))
+                .transpose()
+        }
+
+        let mut table = toml::Table::deserialize(deserializer)?;
+        let events = take(&mut table, "events")?.unwrap_or_default();
+        let dedup_seconds =
+            take(&mut table, "dedup_seconds")?.unwrap_or_else(default_dedup_seconds);
+        let max_per_hour = take(&mut table, "max_per_hour")?;
+        let target =
+            NotificationTarget::deserialize(toml::Value::Table(table)).map_err(de::Error::custom)?;
+
+        Ok(Self {
+            events,
+            dedup_seconds,
+            max_per_hour,
+            target,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum NotificationTarget {
+    Webhook(WebhookConfig),
+    Slack(SlackConfig),
+    Discord(DiscordConfig),
+    Ntfy(NtfyConfig),
+    Gotify(GotifyConfig),
+    Telegram(TelegramConfig),
+    Matrix(MatrixConfig),
+    Email(EmailConfig),
+}
+
+/// Shown in place of secrets
+const REDACTED: &str = "********";
+
+impl NotificationTarget {
+    /// Replace passwords, tokens and URLs that embed a token
+    pub fn redact(&mut self) {
+        let redacted = || REDACTED.to_string();
+        match self {
+            NotificationTarget::Webhook(c) => {
+                c.url = redact_url(&c.url);
+                c.headers.values_mut().for_each(|v| *v = redacted());
+            }
+            NotificationTarget::Slack(c) => c.webhook_url = redacted(),
+            NotificationTarget::Discord(c) => c.webhook_url = redacted(),
+            NotificationTarget::Ntfy(c) => c.token = c.token.as_ref().map(|_| redacted()),
+            NotificationTarget::Gotify(c) => c.token = redacted(),
+            NotificationTarget::Telegram(c) => c.bot_token = redacted(),
+            NotificationTarget::Matrix(c) => c.access_token = redacted(),
+            NotificationTarget::Email(c) => c.password = c.password.as_ref().map(|_| redacted()),
+        }
+    }
+}
+
+/// Keep only the scheme, host and port of a URL, as its credentials, path and
+/// query may all carry a token
+fn redact_url(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if parsed.has_host() => {
+            format!("{}/{}", parsed.origin().ascii_serialization(), REDACTED)
+        }
+        _ => REDACTED.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    pub url: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Body template with `{{placeholder}}` substitution; the event as JSON if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    #[serde(default = "default_content_type")]
+    pub content_type: String,
+}
+
+/// Slack incoming webhook
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SlackConfig {
+    pub webhook_url: String,
+}
+
+/// Discord channel webhook
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DiscordConfig {
+    pub webhook_url: String,
+    /// Override the webhook's default username
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+}
+
+/// ntfy topic, on ntfy.sh or a self-hosted server
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NtfyConfig {
+    #[serde(default = "default_ntfy_server")]
+    pub server: String,
+    pub topic: String,
+    /// Access token for protected topics
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Message priority 1-5; failures default to high, everything else to default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
+}
+
+/// Gotify server application
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GotifyConfig {
+    pub url: String,
+    /// Application token
+    pub token: String,
+    /// Message priority; failures default to 8, everything else to 5
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
+}
+
+/// Telegram bot API
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    /// Chat ID or @channelusername
+    pub chat_id: String,
+    #[serde(default = "default_telegram_api_url")]
+    pub api_url: String,
+}
+
+/// Matrix client-server API
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MatrixConfig {
+    /// Homeserver base URL, e.g. "https://matrix.example.org"
+    pub homeserver: String,
+    pub access_token: String,
+    /// Room ID, e.g. "!abcdef:example.org"
+    pub room_id: String,
+}
+
+/// Email via SMTP
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct EmailConfig {
+    /// SMTP server host name
+    pub host: String,
+    /// SMTP port; 587 for STARTTLS, 465 for implicit TLS and 25 without TLS by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Authentication mechanism; the server's preference of PLAIN and LOGIN if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<SmtpAuth>,
+    pub from: String,
+    pub to: Vec<String>,
+    /// Only send failure alerts once updates have been failing this long
+    #[serde(default = "default_failing_after_seconds")]
+    pub failing_after_seconds: u64,
+    /// Local time ("HH:MM") to send a daily digest of all events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_digest: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Upgrade a plain connection with STARTTLS
+    #[default]
+    Starttls,
+    /// Implicit TLS from the start of the connection
+    Tls,
+    /// No encryption; only for trusted local relays
+    None,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpAuth {
+    Plain,
+    Login,
+}
+
+fn default_failing_after_seconds() -> u64 {
+    900
+}
+
+fn default_dedup_seconds() -> u64 {
+    600
+}
+
+fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+fn default_telegram_api_url() -> String {
+    "https://api.telegram.org".to_string()
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+fn default_content_type() -> String {
+    "application/json".to_string()
+}
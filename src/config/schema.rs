@@ -0,0 +1,159 @@
+use schemars::generate::SchemaSettings;
+use serde_json::{json, Map, Value};
+
+use super::settings::Settings;
+use crate::dns;
+
+/// Keys of a `dns_entries` element that have a list form
+const LIST_KEYS: &[(&str, &str)] = &[
+    ("domain", "domains"),
+    ("record_name", "record_names"),
+    ("record_type", "record_types"),
+];
+
+/// JSON Schema (draft 7) for the configuration file
+pub fn config_schema() -> Value {
+    let schema = SchemaSettings::draft07()
+        .into_generator()
+        .into_root_schema_for::<Settings>();
+    let mut schema = schema.to_value();
+
+    if let Some(root) = schema.as_object_mut() {
+        root.insert("title".to_string(), json!("ipdnsd configuration"));
+        if let Some(properties) = root.get_mut("properties").and_then(Value::as_object_mut) {
+            properties.insert(
+                "include".to_string(),
+                json!({
+                    "description": "Extra configuration files or directories of *.toml files, relative to this file",
+                    "type": "array",
+                    "items": { "type": "string" }
+                }),
+            );
+        }
+    }
+    if let Some(entry) = schema
+        .pointer_mut("/definitions/DnsEntry")
+        .and_then(Value::as_object_mut)
+    {
+        extend_entry(entry);
+    }
+
+    schema
+}
+
+/// Add what the derived `DnsEntry` schema can't express: the supported
+/// providers and record types in any case, their TTL ranges, and the compact
+/// multi-record keys
+fn extend_entry(entry: &mut Map<String, Value>) {
+    let Some(properties) = entry.get_mut("properties").and_then(Value::as_object_mut) else {
+        return;
+    };
+
+    if let Some(provider) = properties.get_mut("provider").and_then(Value::as_object_mut) {
+        provider.insert("pattern".to_string(), json!(any_case(dns::PROVIDERS)));
+    }
+    if let Some(record_type) = properties.get_mut("record_type").and_then(Value::as_object_mut) {
+        record_type.insert("pattern".to_string(), json!(any_case(&["A", "AAAA"])));
+    }
+
+    let mut any_of = Vec::new();
+    for (single, list) in LIST_KEYS {
+        let Some(item) = properties.get(*single).cloned() else {
+            continue;
+        };
+        properties.insert(
+            list.to_string(),
+            json!({
+                "description": format!("Several values for `{}`, one entry is created for each", single),
+                "type": "array",
+                "items": item,
+                "minItems": 1
+            }),
+        );
+        any_of.push(json!({
+            "oneOf": [
+                { "required": [single] },
+                { "required": [list] }
+            ]
+        }));
+    }
+
+    if let Some(required) = entry.get_mut("required").and_then(Value::as_array_mut) {
+        required.retain(|key| !LIST_KEYS.iter().any(|(single, _)| key == single));
+    }
+
+    let ttl_rules = dns::PROVIDERS.iter().map(|provider| {
+        let range = dns::ttl_range(provider);
+        json!({
+            "if": { "properties": { "provider": { "pattern": any_case(&[provider]) } } },
+            "then": {
+                "properties": {
+                    "ttl": { "minimum": range.start(), "maximum": range.end() }
+                }
+            }
+        })
+    });
+    entry.insert(
+        "allOf".to_string(),
+        Value::Array(any_of.into_iter().chain(ttl_rules).collect()),
+    );
+}
+
+/// A pattern matching any of `values` in any case. JSON Schema patterns have
+/// no case-insensitive flag, so each letter becomes a class like `[Aa]`.
+fn any_case(values: &[&str]) -> String {
+    let alternatives: Vec<String> = values
+        .iter()
+        .map(|value| {
+            value
+                .chars()
+                .map(|c| match c {
+                    c if c.is_ascii_alphabetic() => {
+                        format!("[{}{}]", c.to_ascii_uppercase(), c.to_ascii_lowercase())
+                    }
+                    c if c.is_ascii_alphanumeric() => c.to_string(),
+                    c => format!("\\{}", c),
+                })
+                .collect()
+        })
+        .collect();
+    format!("^(?:{})$", alternatives.join("|"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_schema() {
+        let schema = config_schema();
+
+        assert_eq!(schema["title"], "ipdnsd configuration");
+        let entry = &schema["definitions"]["DnsEntry"];
+        assert_eq!(
+            entry["properties"]["provider"]["pattern"],
+            "^(?:[Gg][Oo][Dd][Aa][Dd][Dd][Yy])$"
+        );
+        assert_eq!(entry["properties"]["record_type"]["pattern"], "^(?:[Aa]|[Aa][Aa][Aa][Aa])$");
+        assert!(entry["properties"]["provider"].get("enum").is_none());
+        assert_eq!(entry["properties"]["record_names"]["type"], "array");
+        assert_eq!(entry["required"], json!(["provider", "ip_source"]));
+        assert_eq!(
+            entry["allOf"][3]["then"]["properties"]["ttl"],
+            json!({ "minimum": 600, "maximum": 604800 })
+        );
+        assert!(schema["definitions"]["IpSource"]["anyOf"].is_array());
+        assert!(schema["definitions"]["NotificationConfig"].is_object());
+    }
+
+    #[test]
+    fn test_shipped_schema_is_current() {
+        let shipped: Value =
+            serde_json::from_str(include_str!("../../config/ipdnsd.schema.json")).unwrap();
+        assert_eq!(
+            shipped,
+            config_schema(),
+            "config/ipdnsd.schema.json is outdated, regenerate it with `ipdnsd config schema`"
+        );
+    }
+}
@@ -0,0 +1,754 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use serde::de::{DeserializeOwned, IntoDeserializer};
+use toml_edit::{ImDocument, Item, Value};
+
+use super::env::apply_overrides;
+use super::expand::expand_entry;
+use super::notifications::NotificationConfig;
+use super::settings::{
+    CredentialBackend, CredentialsConfig, DaemonConfig, DnsEntry, ExternalIpConfig, Settings,
+};
+use crate::{dns, secrets};
+use crate::notify::create_notifier;
+
+/// Shortest accepted check interval
+pub const MIN_INTERVAL_SECONDS: u64 = 10;
+
+/// A problem found in a configuration file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// Drop-in file the problem is in, `None` for the main file
+    pub file: Option<PathBuf>,
+    /// 1-based line and column, if the problem has a position
+    pub location: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        match self.location {
+            Some((line, column)) => write!(f, "line {}, column {}: {}", line, column, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Every problem found in a configuration file
+#[derive(Debug, thiserror::Error)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration file {}:", self.path.display())?;
+        for diagnostic in &self.diagnostics {
+            write!(f, "\n  {}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+/// Keys a drop-in file may set
+const DROP_IN_KEYS: &[&str] = &["dns_entries", "notifications"];
+
+/// A configuration file and its contents
+#[derive(Debug, Clone)]
+pub struct ConfigSource {
+    pub path: PathBuf,
+    pub text: String,
+}
+
+/// Whether credentials are stored for a provider and optional credential profile
+pub type HasCredentials<'a> = dyn Fn(&str, Option<&str>) -> bool + 'a;
+
+/// Parse and check a configuration file, reporting every problem found
+/// rather than stopping at the first one.
+///
+/// With `has_credentials`, the providers and credential profiles used by
+/// entries must also have stored credentials.
+pub fn validate(
+    source: &str,
+    has_credentials: Option<&HasCredentials>,
+) -> Result<Settings, Vec<Diagnostic>> {
+    validate_with_overrides(source, &[], has_credentials)
+}
+
+/// Like [`validate`], with `IPDNSD_*` style overrides applied on top of the
+/// file (see [`env_overrides`](super::env_overrides))
+pub fn validate_with_overrides(
+    source: &str,
+    overrides: &[(String, String)],
+    has_credentials: Option<&HasCredentials>,
+) -> Result<Settings, Vec<Diagnostic>> {
+    let main = ConfigSource {
+        path: PathBuf::new(),
+        text: source.to_string(),
+    };
+    validate_sources(&main, &[], overrides, has_credentials)
+}
+
+/// Check a main configuration file together with its drop-in files, whose
+/// `dns_entries` and `notifications` are appended in order
+pub fn validate_sources(
+    main: &ConfigSource,
+    drop_ins: &[ConfigSource],
+    overrides: &[(String, String)],
+    has_credentials: Option<&HasCredentials>,
+) -> Result<Settings, Vec<Diagnostic>> {
+    let mut checker = Checker {
+        sources: std::iter::once(main).chain(drop_ins).collect(),
+        current: 0,
+        diagnostics: Vec::new(),
+    };
+
+    let Some(doc) = checker.parse_document() else {
+        return Err(checker.diagnostics);
+    };
+    let mut root = doc.as_table().clone();
+    for error in apply_overrides(&mut root, overrides) {
+        checker.report(None, error);
+    }
+    let root = &root;
+
+    let mut daemon: Option<DaemonConfig> = None;
+    let mut external_ip: Option<ExternalIpConfig> = None;
+    let mut dns_entries = Vec::new();
+    let mut notifications = Vec::new();
+    let mut hooks = None;
+    let mut history = None;
+    let mut credentials: Option<CredentialsConfig> = None;
+
+    for (key, item) in root.iter() {
+        match key {
+            "daemon" => daemon = checker.parse(item),
+            "external_ip" => external_ip = checker.parse(item),
+            "dns_entries" => dns_entries = checker.parse_entries(item),
+            "notifications" => notifications = checker.parse_array::<NotificationConfig>(item),
+            "hooks" => hooks = checker.parse(item),
+            "history" => history = checker.parse(item),
+            "credentials" => credentials = checker.parse(item),
+            "include" => {
+                checker.parse::<Vec<PathBuf>>(item);
+            }
+            _ => checker.report(
+                root.key(key).and_then(|key| key.span()),
+                format!("unknown key `{}`", key),
+            ),
+        }
+    }
+
+    for index in 1..checker.sources.len() {
+        checker.current = index;
+        let Some(doc) = checker.parse_document() else {
+            continue;
+        };
+        let root = doc.as_table();
+
+        for (key, item) in root.iter() {
+            match key {
+                "dns_entries" => dns_entries.extend(checker.parse_entries(item)),
+                "notifications" => {
+                    notifications.extend(checker.parse_array::<NotificationConfig>(item))
+                }
+                _ => checker.report(
+                    root.key(key).and_then(|key| key.span()),
+                    format!(
+                        "unknown key `{}` (drop-in files can only set {})",
+                        key,
+                        DROP_IN_KEYS.join(", ")
+                    ),
+                ),
+            }
+        }
+    }
+    checker.current = 0;
+
+    if dns_entries.is_empty() && !root.contains_key("dns_entries") {
+        checker.report(None, "missing dns_entries");
+    }
+
+    if let Some(daemon) = &daemon {
+        if daemon.interval_seconds < MIN_INTERVAL_SECONDS {
+            checker.report(
+                span(root.get("daemon").and_then(|d| d.get("interval_seconds"))),
+                format!(
+                    "interval_seconds must be at least {}, got {}",
+                    MIN_INTERVAL_SECONDS, daemon.interval_seconds
+                ),
+            );
+        }
+    }
+
+    if let Some(consensus) = external_ip.as_ref().and_then(|e| e.consensus.as_ref()) {
+        let configured = external_ip.as_ref().map_or(0, |e| e.services.len());
+        let field = |key| {
+            span(
+                root.get("external_ip")
+                    .and_then(|e| e.get("consensus"))
+                    .and_then(|c| c.get(key)),
+            )
+        };
+        if consensus.services > configured {
+            checker.report(
+                field("services"),
+                format!(
+                    "consensus services is {} but only {} external IP services are configured",
+                    consensus.services, configured
+                ),
+            );
+        }
+        if consensus.quorum < consensus.min_quorum() || consensus.quorum > consensus.services {
+            checker.report(
+                field("quorum"),
+                format!(
+                    "quorum must be more than half of the {} consensus services and at most all of them, got {}",
+                    consensus.services, consensus.quorum
+                ),
+            );
+        }
+    }
+
+    if let Some(credentials) = &credentials {
+        if credentials.store == CredentialBackend::SecretService && !cfg!(target_os = "linux") {
+            checker.report(
+                span(root.get("credentials").and_then(|c| c.get("store"))),
+                "the secret-service credential store is only available on Linux",
+            );
+        }
+    }
+
+    checker.check_entries(&dns_entries, has_credentials);
+
+    for notification in &notifications {
+        if let Err(e) = create_notifier(&notification.value) {
+            checker.report_at(
+                notification.source,
+                notification.item.span(),
+                format!("invalid notification target: {:#}", e),
+            );
+        }
+    }
+
+    if !checker.diagnostics.is_empty() {
+        return Err(checker.diagnostics);
+    }
+
+    let dns_entries = dns_entries
+        .into_iter()
+        .map(|entry| {
+            let mut value = entry.value;
+            if let Some(base) = checker.sources[entry.source].path.parent() {
+                value.ip_source.resolve_paths(base);
+            }
+            value
+        })
+        .collect();
+
+    Ok(Settings {
+        daemon: daemon.unwrap_or_default(),
+        external_ip: external_ip.unwrap_or_default(),
+        dns_entries,
+        notifications: notifications.into_iter().map(|n| n.value).collect(),
+        hooks: hooks.unwrap_or_default(),
+        history: history.unwrap_or_default(),
+        credentials: credentials.unwrap_or_default(),
+    })
+}
+
+/// An array element and the file it came from
+struct Parsed<T> {
+    value: T,
+    item: Item,
+    source: usize,
+}
+
+struct Checker<'a> {
+    /// The main file, followed by the drop-ins
+    sources: Vec<&'a ConfigSource>,
+    /// Index of the file being checked
+    current: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker<'_> {
+    fn report(&mut self, span: Option<Range<usize>>, message: impl Into<String>) {
+        self.report_at(self.current, span, message);
+    }
+
+    fn report_at(&mut self, source: usize, span: Option<Range<usize>>, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic {
+            file: (source > 0).then(|| self.sources[source].path.clone()),
+            location: span.map(|span| line_col(&self.sources[source].text, span.start)),
+            message: message.into(),
+        });
+    }
+
+    fn parse_document(&mut self) -> Option<ImDocument<String>> {
+        match ImDocument::parse(self.sources[self.current].text.clone()) {
+            Ok(doc) => Some(doc),
+            Err(e) => {
+                self.report(e.span(), e.message().trim());
+                None
+            }
+        }
+    }
+
+    /// Line an item starts on, with the file if it isn't `relative_to`
+    fn describe_location(&self, source: usize, item: &Item, relative_to: usize) -> String {
+        let line = item
+            .span()
+            .map(|span| line_col(&self.sources[source].text, span.start).0)
+            .unwrap_or_default();
+        if source == relative_to {
+            format!("on line {}", line)
+        } else {
+            format!("in {} on line {}", self.sources[source].path.display(), line)
+        }
+    }
+
+    fn parse<T: DeserializeOwned>(&mut self, item: &Item) -> Option<T> {
+        let value = item.clone().into_value().ok()?;
+        match T::deserialize(value.into_deserializer()) {
+            Ok(parsed) => Some(parsed),
+            Err(e) => {
+                self.report(e.span().or_else(|| item.span()), e.message());
+                None
+            }
+        }
+    }
+
+    /// Parse `dns_entries`, expanding the compact multi-record syntax
+    fn parse_entries(&mut self, item: &Item) -> Vec<Parsed<DnsEntry>> {
+        let mut entries = Vec::new();
+        for element in self.elements(item) {
+            match expand_entry(&element) {
+                Ok(expanded) => entries.extend(expanded.iter().filter_map(|e| self.parse_element(e))),
+                Err((span, message)) => self.report(span.or_else(|| element.span()), message),
+            }
+        }
+        entries
+    }
+
+    /// Parse each element separately so every invalid element is reported
+    fn parse_array<T: DeserializeOwned>(&mut self, item: &Item) -> Vec<Parsed<T>> {
+        self.elements(item)
+            .iter()
+            .filter_map(|element| self.parse_element(element))
+            .collect()
+    }
+
+    fn parse_element<T: DeserializeOwned>(&mut self, element: &Item) -> Option<Parsed<T>> {
+        self.parse(element).map(|value| Parsed {
+            value,
+            item: element.clone(),
+            source: self.current,
+        })
+    }
+
+    fn elements(&mut self, item: &Item) -> Vec<Item> {
+        match item {
+            Item::ArrayOfTables(tables) => tables.iter().map(|t| Item::Table(t.clone())).collect(),
+            Item::Value(Value::Array(array)) => {
+                array.iter().map(|v| Item::Value(v.clone())).collect()
+            }
+            _ => {
+                self.report(item.span(), "expected an array of tables");
+                Vec::new()
+            }
+        }
+    }
+
+    fn check_entries(
+        &mut self,
+        entries: &[Parsed<DnsEntry>],
+        has_credentials: Option<&HasCredentials>,
+    ) {
+        let mut seen: HashMap<(String, String, String), &Parsed<DnsEntry>> = HashMap::new();
+        let mut checked_providers = HashSet::new();
+
+        for parsed in entries {
+            let (entry, item) = (&parsed.value, &parsed.item);
+            self.current = parsed.source;
+            let field = |key: &str| span(item.get(key));
+            let provider = secrets::provider_key(&entry.provider);
+
+            if !dns::PROVIDERS.contains(&provider.as_str()) {
+                self.report(
+                    field("provider"),
+                    format!(
+                        "unsupported provider `{}` (supported: {})",
+                        entry.provider,
+                        dns::PROVIDERS.join(", ")
+                    ),
+                );
+            } else if let Some(profile) = entry
+                .credentials
+                .as_deref()
+                .filter(|profile| !secrets::is_valid_profile(profile))
+            {
+                self.report(
+                    field("credentials"),
+                    format!(
+                        "invalid credential profile name `{}`; use letters, digits, `-`, `_` and `.`",
+                        profile
+                    ),
+                );
+            } else if let Some(has_credentials) = has_credentials {
+                let profile = entry.credentials.as_deref();
+                if checked_providers.insert((provider.clone(), entry.credentials.clone()))
+                    && !has_credentials(&provider, profile)
+                {
+                    let key = if profile.is_some() { "credentials" } else { "provider" };
+                    self.report(
+                        field(key),
+                        format!(
+                            "no credentials stored for provider `{}`; run `{}`",
+                            secrets::describe(&entry.provider, profile),
+                            secrets::set_key_command(&provider, profile)
+                        ),
+                    );
+                }
+            }
+
+            let record_type = entry.record_type.to_uppercase();
+            if record_type != "A" && record_type != "AAAA" {
+                self.report(
+                    field("record_type"),
+                    format!(
+                        "unsupported record type `{}` (expected A or AAAA)",
+                        entry.record_type
+                    ),
+                );
+            }
+
+            if let Some(ttl) = entry.ttl {
+                let range = dns::ttl_range(&provider);
+                if !range.contains(&ttl) {
+                    self.report(
+                        field("ttl"),
+                        format!(
+                            "ttl {} is outside the range {}-{} accepted by {}",
+                            ttl,
+                            range.start(),
+                            range.end(),
+                            entry.provider
+                        ),
+                    );
+                }
+            }
+
+            let key = (
+                entry.domain.trim_end_matches('.').to_lowercase(),
+                entry.record_name.to_lowercase(),
+                record_type.clone(),
+            );
+            match seen.get(&key) {
+                Some(first) => {
+                    let first = self.describe_location(first.source, &first.item, parsed.source);
+                    self.report(
+                        item.span(),
+                        format!(
+                            "duplicate entry for {} ({}), first defined {}",
+                            entry.fqdn(),
+                            record_type,
+                            first
+                        ),
+                    );
+                }
+                None => {
+                    seen.insert(key, parsed);
+                }
+            }
+        }
+        self.current = 0;
+    }
+}
+
+fn span(item: Option<&Item>) -> Option<Range<usize>> {
+    item.and_then(Item::span)
+}
+
+/// 1-based line and column of a byte offset
+fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rfind('\n')
+        .map_or(before, |newline| &before[newline + 1..])
+        .chars()
+        .count()
+        + 1;
+
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(source: &str) -> Vec<String> {
+        validate(source, Some(&|provider: &str, _: Option<&str>| provider != "godaddy"))
+            .unwrap_err()
+            .iter()
+            .map(|d| d.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_config() {
+        let settings = validate(
+            r#"
+[daemon]
+interval_seconds = 60
+
+[[dns_entries]]
+provider = "godaddy"
+domain = "example.com"
+record_name = "@"
+record_type = "A"
+ip_source = "external"
+ttl = 600
+"#,
+            Some(&|_: &str, _: Option<&str>| true),
+        )
+        .unwrap();
+        assert_eq!(settings.dns_entries.len(), 1);
+        assert_eq!(settings.daemon.interval_seconds, 60);
+    }
+
+    #[test]
+    fn test_credential_profiles() {
+        let source = r#"
+[[dns_entries]]
+provider = "godaddy"
+credentials = "client-a"
+domain = "example.com"
+record_name = "@"
+record_type = "A"
+ip_source = "external"
+
+[[dns_entries]]
+provider = "godaddy"
+credentials = "client-b"
+domain = "example.org"
+record_name = "@"
+record_type = "A"
+ip_source = "external"
+
+[[dns_entries]]
+provider = "godaddy"
+credentials = "client c"
+domain = "example.net"
+record_name = "@"
+record_type = "A"
+ip_source = "external"
+"#;
+        let messages: Vec<String> = validate(
+            source,
+            Some(&|_: &str, profile: Option<&str>| profile == Some("client-a")),
+        )
+        .unwrap_err()
+        .iter()
+        .map(|d| d.to_string())
+        .collect();
+
+        assert_eq!(
+            messages,
+            [
+                "line 12, column 15: no credentials stored for provider `godaddy (profile client-b)`; run `ipdnsd set-key godaddy --profile client-b`",
+                "line 20, column 15: invalid credential profile name `client c`; use letters, digits, `-`, `_` and `.`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_provider_name_case() {
+        let source = r#"
+[[dns_entries]]
+provider = "GoDaddy"
+domain = "example.com"
+record_name = "@"
+record_type = "A"
+ip_source = "external"
+"#;
+        let stored = |provider: &str, _: Option<&str>| provider == "godaddy";
+        assert!(validate(source, Some(&stored)).is_ok());
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let diagnostics = validate("[daemon]\ninterval_seconds = = 5\n", None).unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].location, Some((2, 20)));
+    }
+
+    #[test]
+    fn test_reports_all_problems() {
+        let messages = messages(
+            r#"
+[daemon]
+interval_seconds = 0
+
+[history]
+keeps = 3
+
+[[dns_entries]]
+provider = "godaddy"
+domain = "example.com"
+record_name = "www"
+record_type = "A"
+ip_source = "external"
+ttl = 60
+
+[[dns_entries]]
+provider = "cloudflare"
+domain = "example.com"
+record_name = "vpn"
+record_type = "MX"
+ip_source = "external"
+
+[[dns_entries]]
+provider = "godaddy"
+domain = "Example.com"
+record_name = "WWW"
+record_type = "A"
+ip_source = "internal"
+tll = 600
+
+[[dns_entries]]
+provider = "godaddy"
+domain = "example.com"
+record_name = "www"
+record_type = "A"
+ip_source = "internal"
+
+[[notifications]]
+type = "email"
+host = "smtp.example.com"
+from = "not an address"
+to = ["ops@example.com"]
+"#,
+        );
+
+        assert_eq!(
+            messages,
+            vec![
+                "line 6, column 1: unknown field `keeps`, expected one of `enabled`, `path`, `max_size_kb`, `keep`".to_string(),
+                "line 29, column 1: unknown field `tll`, expected one of `provider`, `credentials`, `domain`, `record_name`, `record_type`, `ip_source`, `ttl`, `policy`, `damping`, `on_change`, `on_failure`".to_string(),
+                "line 3, column 20: interval_seconds must be at least 10, got 0".to_string(),
+                "line 9, column 12: no credentials stored for provider `godaddy`; run `ipdnsd set-key godaddy`".to_string(),
+                "line 14, column 7: ttl 60 is outside the range 600-604800 accepted by godaddy".to_string(),
+                "line 17, column 12: unsupported provider `cloudflare` (supported: godaddy)".to_string(),
+                "line 20, column 15: unsupported record type `MX` (expected A or AAAA)".to_string(),
+                "line 31, column 1: duplicate entry for www.example.com (A), first defined on line 8".to_string(),
+                "line 38, column 1: invalid notification target: Invalid sender address: not an address: Invalid input".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_consensus_quorum() {
+        let source = |services: usize, quorum: usize| {
+            format!(
+                r#"
+dns_entries = []
+
+[external_ip]
+services = [{{ url = "https://a.example" }}, {{ url = "https://b.example" }}, {{ url = "https://c.example" }}, {{ url = "https://d.example" }}]
+
+[external_ip.consensus]
+services = {}
+quorum = {}
+"#,
+                services, quorum
+            )
+        };
+
+        assert!(validate(&source(4, 3), None).is_ok());
+        assert_eq!(
+            messages(&source(4, 2)),
+            ["line 9, column 10: quorum must be more than half of the 4 consensus services and at most all of them, got 2"]
+        );
+        assert_eq!(
+            messages(&source(5, 3)),
+            ["line 8, column 12: consensus services is 5 but only 4 external IP services are configured"]
+        );
+    }
+
+    #[test]
+    fn test_overrides() {
+        let overrides = [
+            ("IPDNSD_DAEMON__INTERVAL_SECONDS", "5"),
+            ("IPDNSD_ENTRY__PROVIDER", "godaddy"),
+            ("IPDNSD_ENTRY__DOMAIN", "example.com"),
+            ("IPDNSD_ENTRY__RECORD_NAME", "@"),
+            ("IPDNSD_ENTRY__RECORD_TYPE", "A"),
+            ("IPDNSD_ENTRY__IP_SOURCE", "external"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        // An entry can come entirely from the environment
+        let diagnostics = validate_with_overrides("", &overrides, None).unwrap_err();
+        assert_eq!(
+            diagnostics.iter().map(|d| d.to_string()).collect::<Vec<_>>(),
+            vec!["interval_seconds must be at least 10, got 5".to_string()]
+        );
+
+        let settings = validate_with_overrides("", &overrides[1..], None).unwrap();
+        assert_eq!(settings.daemon.interval_seconds, 300);
+        assert_eq!(settings.dns_entries[0].fqdn(), "example.com");
+    }
+
+    #[test]
+    fn test_compact_entries() {
+        let source = r#"
+[[dns_entries]]
+provider = "godaddy"
+domain = "example.com"
+record_names = ["@", "www"]
+record_types = ["A", "MX"]
+ip_source = "external"
+"#;
+        let diagnostics = validate(source, None).unwrap_err();
+        let locations: Vec<_> = diagnostics.iter().map(|d| d.location).collect();
+        // One problem for each expanded entry, at the array element
+        assert_eq!(locations, [Some((6, 22)), Some((6, 22))]);
+
+        let settings = validate(&source.replace("\"MX\"", "\"AAAA\""), None).unwrap();
+        let records: Vec<_> = settings
+            .dns_entries
+            .iter()
+            .map(|e| format!("{} {}", e.fqdn(), e.record_type))
+            .collect();
+        assert_eq!(
+            records,
+            [
+                "example.com A",
+                "example.com AAAA",
+                "www.example.com A",
+                "www.example.com AAAA",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_top_level_key() {
+        let messages = messages("dns_entry = []\n[daemon]\n");
+        assert_eq!(
+            messages,
+            vec![
+                "line 1, column 1: unknown key `dns_entry`".to_string(),
+                "missing dns_entries".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use crate::config::DampingConfig;
+
+const RATE_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Hold-down and rate-limit state for one DNS entry
+#[derive(Debug, Default)]
+pub struct Damper {
+    candidate: Option<Candidate>,
+    updates: VecDeque<Instant>,
+}
+
+#[derive(Debug)]
+struct Candidate {
+    ip: IpAddr,
+    first_seen: Instant,
+    checks: u32,
+}
+
+impl Damper {
+    /// Record an observation of a new address and return whether it has been
+    /// stable long enough to be published
+    pub fn observe(&mut self, ip: IpAddr, now: Instant, config: &DampingConfig) -> bool {
+        let candidate = match &mut self.candidate {
+            Some(candidate) if candidate.ip == ip => {
+                candidate.checks += 1;
+                candidate
+            }
+            _ => self.candidate.insert(Candidate {
+                ip,
+                first_seen: now,
+                checks: 1,
+            }),
+        };
+
+        candidate.checks >= config.stable_checks
+            && now.duration_since(candidate.first_seen)
+                >= Duration::from_secs(config.stable_seconds)
+    }
+
+    /// Forget any pending address, e.g. because the address bounced back
+    pub fn reset(&mut self) {
+        self.candidate = None;
+    }
+
+    /// Whether another record update fits within the hourly limit
+    pub fn update_allowed(&mut self, now: Instant, config: &DampingConfig) -> bool {
+        while let Some(oldest) = self.updates.front() {
+            if now.duration_since(*oldest) < RATE_WINDOW {
+                break;
+            }
+            self.updates.pop_front();
+        }
+
+        config
+            .max_updates_per_hour
+            .is_none_or(|max| self.updates.len() < max as usize)
+    }
+
+    pub fn record_update(&mut self, now: Instant) {
+        self.updates.push_back(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_no_damping_accepts_immediately() {
+        let mut damper = Damper::default();
+        assert!(damper.observe(ip("198.51.100.1"), Instant::now(), &DampingConfig::default()));
+    }
+
+    #[test]
+    fn test_stable_checks() {
+        let config = DampingConfig {
+            stable_checks: 3,
+            ..Default::default()
+        };
+        let now = Instant::now();
+        let mut damper = Damper::default();
+
+        assert!(!damper.observe(ip("198.51.100.1"), now, &config));
+        assert!(!damper.observe(ip("198.51.100.1"), now, &config));
+        // A bounce to another address restarts the count
+        assert!(!damper.observe(ip("198.51.100.2"), now, &config));
+        assert!(!damper.observe(ip("198.51.100.1"), now, &config));
+        assert!(!damper.observe(ip("198.51.100.1"), now, &config));
+        assert!(damper.observe(ip("198.51.100.1"), now, &config));
+    }
+
+    #[test]
+    fn test_stable_seconds() {
+        let config = DampingConfig {
+            stable_seconds: 600,
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let mut damper = Damper::default();
+
+        assert!(!damper.observe(ip("198.51.100.1"), start, &config));
+        assert!(!damper.observe(ip("198.51.100.1"), start + Duration::from_secs(300), &config));
+        assert!(damper.observe(ip("198.51.100.1"), start + Duration::from_secs(600), &config));
+    }
+
+    #[test]
+    fn test_max_updates_per_hour() {
+        let config = DampingConfig {
+            max_updates_per_hour: Some(2),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let mut damper = Damper::default();
+
+        assert!(damper.update_allowed(start, &config));
+        damper.record_update(start);
+        assert!(damper.update_allowed(start, &config));
+        damper.record_update(start + Duration::from_secs(60));
+        assert!(!damper.update_allowed(start + Duration::from_secs(120), &config));
+        assert!(damper.update_allowed(start + Duration::from_secs(3600), &config));
+    }
+}
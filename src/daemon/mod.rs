@@ -1,3 +1,5 @@
+mod damping;
 mod service;
+mod watch;
 
 pub use service::{install_service, run, uninstall_service};
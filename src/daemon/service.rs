@@ -1,18 +1,23 @@
 use std::collections::HashMap;
 use std::net::IpAddr;
+use std::path::Path;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
-use tokio::signal;
-use tokio::sync::watch;
+use tokio::sync::{mpsc, watch};
 use tracing::{error, info, warn};
 
-use crate::config::{DnsEntry, Settings};
-use crate::dns::{create_provider, DnsProvider, DnsRecord};
+use crate::config::{CommandSpec, DnsEntry, EventKind, Settings};
+use crate::dns::{create_provider, DnsProvider, DnsRecord, ProviderError};
+use crate::history::{HistoryRecord, Journal, RecordKind};
 use crate::ip;
+use crate::notify::{run_hook, Dispatcher, Event};
 use crate::secrets;
 
+use super::damping::Damper;
+use super::watch::watch_ip_files;
+
 pub async fn run(settings: Settings) -> Result<()> {
     let interval = Duration::from_secs(settings.daemon.interval_seconds);
 
@@ -27,32 +32,51 @@ pub async fn run(settings: Settings) -> Result<()> {
         let _ = shutdown_tx.send(true);
     });
 
-    // Cache for last known IPs
-    let mut ip_cache: HashMap<String, IpAddr> = HashMap::new();
+    // Last published IPs and damping state per entry
+    let mut states: HashMap<String, EntryState> = HashMap::new();
 
     // Pre-load providers
+    let store = secrets::open_store(settings.credentials.store)?;
     let mut providers: HashMap<String, Arc<dyn DnsProvider>> = HashMap::new();
     for entry in &settings.dns_entries {
-        if !providers.contains_key(&entry.provider) {
-            match secrets::get_credentials(&entry.provider) {
-                Ok(creds) => match create_provider(&entry.provider, creds) {
-                    Ok(provider) => {
-                        providers.insert(entry.provider.clone(), provider);
-                    }
-                    Err(e) => {
-                        error!("Failed to create provider {}: {}", entry.provider, e);
-                    }
-                },
+        let key = provider_key(entry);
+        if providers.contains_key(&key) {
+            continue;
+        }
+
+        let profile = entry.credentials.as_deref();
+        match store.credentials(&entry.provider, profile) {
+            Ok(creds) => match create_provider(&entry.provider, creds) {
+                Ok(provider) => {
+                    providers.insert(key, provider);
+                }
                 Err(e) => {
-                    error!(
-                        "Failed to get credentials for {}: {}",
-                        entry.provider, e
-                    );
+                    error!("Failed to create provider {}: {}", entry.provider, e);
                 }
+            },
+            Err(e) => {
+                error!(
+                    "Failed to get credentials for {}: {}",
+                    secrets::describe(&entry.provider, profile),
+                    e
+                );
             }
         }
     }
 
+    let dispatcher = Dispatcher::new(&settings.notifications);
+    let journal = Journal::new(&settings.history);
+
+    // Re-check as soon as a file used as IP source changes
+    let (file_tx, mut file_rx) = mpsc::channel(1);
+    let _watcher = match watch_ip_files(&settings.dns_entries, file_tx) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            warn!("File IP sources will only be checked every interval: {:#}", e);
+            None
+        }
+    };
+
     info!(
         "Daemon started. Monitoring {} DNS entries with {} second interval",
         settings.dns_entries.len(),
@@ -60,13 +84,17 @@ pub async fn run(settings: Settings) -> Result<()> {
     );
 
     // Initial check
-    check_and_update(&settings.dns_entries, &providers, &mut ip_cache).await;
+    check_and_update(&settings, &providers, &dispatcher, &journal, &mut states).await;
 
     // Main loop
     loop {
         tokio::select! {
             _ = tokio::time::sleep(interval) => {
-                check_and_update(&settings.dns_entries, &providers, &mut ip_cache).await;
+                check_and_update(&settings, &providers, &dispatcher, &journal, &mut states).await;
+            }
+            Some(()) = file_rx.recv() => {
+                info!("IP source file changed, checking DNS entries");
+                check_and_update(&settings, &providers, &dispatcher, &journal, &mut states).await;
             }
             _ = shutdown_rx.changed() => {
                 if *shutdown_rx.borrow() {
@@ -80,44 +108,130 @@ pub async fn run(settings: Settings) -> Result<()> {
     Ok(())
 }
 
+/// What the daemon remembers about a DNS entry between checks
+#[derive(Default)]
+struct EntryState {
+    /// Address last confirmed to be in DNS
+    published: Option<IpAddr>,
+    /// Address returned by the IP source on the previous check
+    observed: Option<IpAddr>,
+    damper: Damper,
+    /// Address an `IpChanged` event was last sent for
+    announced: Option<IpAddr>,
+    /// Whether the last update attempt failed
+    failing: bool,
+}
+
+impl EntryState {
+    /// Note that DNS holds `ip`, returning whether that ends a failure
+    fn confirm(&mut self, ip: IpAddr) -> bool {
+        self.published = Some(ip);
+        self.damper.reset();
+        std::mem::take(&mut self.failing)
+    }
+}
+
+/// Providers are created once per provider and credential profile
+fn provider_key(entry: &DnsEntry) -> String {
+    let provider = secrets::provider_key(&entry.provider);
+    match &entry.credentials {
+        Some(profile) => format!("{}:{}", provider, profile),
+        None => provider,
+    }
+}
+
+fn entry_key(entry: &DnsEntry) -> String {
+    format!(
+        "{}:{}:{}:{}",
+        entry.ip_source, entry.domain, entry.record_name, entry.record_type
+    )
+}
+
 async fn check_and_update(
-    entries: &[DnsEntry],
+    settings: &Settings,
     providers: &HashMap<String, Arc<dyn DnsProvider>>,
-    ip_cache: &mut HashMap<String, IpAddr>,
+    dispatcher: &Dispatcher,
+    journal: &Journal,
+    states: &mut HashMap<String, EntryState>,
 ) {
-    for entry in entries {
-        let cache_key = format!("{}:{}:{}", entry.ip_source, entry.domain, entry.record_name);
+    check_entries(settings, providers, dispatcher, journal, states).await;
+    dispatcher.tick();
+}
+
+async fn check_entries(
+    settings: &Settings,
+    providers: &HashMap<String, Arc<dyn DnsProvider>>,
+    dispatcher: &Dispatcher,
+    journal: &Journal,
+    states: &mut HashMap<String, EntryState>,
+) {
+    for entry in &settings.dns_entries {
+        let state = states.entry(entry_key(entry)).or_default();
+        let damping = entry.damping.as_ref().unwrap_or(&settings.daemon.damping);
+        let now = Instant::now();
 
         // Get current IP
-        let current_ip = match ip::get_ip(&entry.ip_source).await {
-            Ok(ip) => ip,
+        let (current_ip, selected) = match ip::get_ip(&entry.ip_source, &settings.external_ip, entry.family()).await {
+            Ok(resolved) => resolved,
             Err(e) => {
                 warn!(
-                    "Failed to get {:?} IP for {}.{}: {}",
+                    "Failed to get {} IP for {}.{}: {}",
                     entry.ip_source, entry.record_name, entry.domain, e
                 );
                 continue;
             }
         };
 
-        // Check if IP changed
-        let ip_changed = match ip_cache.get(&cache_key) {
-            Some(cached_ip) => *cached_ip != current_ip,
-            None => true, // First run, need to check DNS
-        };
+        if state.observed != Some(current_ip) {
+            journal.record(HistoryRecord::new(
+                entry,
+                RecordKind::Observed {
+                    old_ip: state.observed,
+                    new_ip: current_ip,
+                    source: entry.ip_source.to_string(),
+                },
+            ));
+            state.observed = Some(current_ip);
+        }
 
-        if !ip_changed {
+        // Never publish an address the entry's policy rejects
+        if let Err(e) = ip::check_address(entry, &current_ip, selected) {
+            warn!(
+                "Refusing to publish {} for {}.{}: {}",
+                current_ip, entry.record_name, entry.domain, e
+            );
             continue;
         }
 
-        // Update cache
-        ip_cache.insert(cache_key.clone(), current_ip);
+        // Check if IP changed
+        match state.published {
+            Some(published) if published == current_ip => {
+                state.announced = None;
+                if state.confirm(current_ip) {
+                    dispatcher.notify(
+                        Event::new(EventKind::Recovered, entry).with_ips(None, current_ip),
+                    );
+                }
+                continue;
+            }
+            Some(_) if !state.damper.observe(current_ip, now, damping) => {
+                info!(
+                    "IP for {}.{} changed to {}, waiting for it to stabilise",
+                    entry.record_name, entry.domain, current_ip
+                );
+                continue;
+            }
+            _ => {} // Changed and stable, or first run: need to check DNS
+        }
 
         // Get provider
-        let provider = match providers.get(&entry.provider) {
+        let provider = match providers.get(&provider_key(entry)) {
             Some(p) => p,
             None => {
-                warn!("Provider {} not available", entry.provider);
+                warn!(
+                    "Provider {} not available",
+                    secrets::describe(&entry.provider, entry.credentials.as_deref())
+                );
                 continue;
             }
         };
@@ -150,9 +264,32 @@ async fn check_and_update(
                 "DNS record {}.{} already set to {}",
                 entry.record_name, entry.domain, current_ip
             );
+            if state.confirm(current_ip) {
+                dispatcher.notify(
+                    Event::new(EventKind::Recovered, entry).with_ips(None, current_ip),
+                );
+            }
+            continue;
+        }
+
+        if !state.damper.update_allowed(now, damping) {
+            warn!(
+                "Update limit reached for {}.{}, postponing change to {}",
+                entry.record_name, entry.domain, current_ip
+            );
             continue;
         }
 
+        // Announce a change once, not again on every retry of its update
+        if let Some(published) = state.published {
+            if state.announced != Some(current_ip) {
+                state.announced = Some(current_ip);
+                dispatcher.notify(
+                    Event::new(EventKind::IpChanged, entry).with_ips(Some(published), current_ip),
+                );
+            }
+        }
+
         // Update DNS
         let new_record = DnsRecord::new(
             &entry.record_name,
@@ -166,23 +303,89 @@ async fn check_and_update(
             entry.record_name, entry.domain, dns_record.data, current_ip
         );
 
-        match provider.update_record(&entry.domain, &new_record).await {
-            Ok(()) => {
+        let old_ip = dns_record.data.parse().ok().or(state.published);
+        let started = Instant::now();
+        let result = provider.update_record(&entry.domain, &new_record).await;
+        journal.record(HistoryRecord::new(
+            entry,
+            RecordKind::Update {
+                provider: entry.provider.clone(),
+                old_value: Some(dns_record.data.clone()),
+                new_value: current_ip_str.clone(),
+                success: result.is_ok(),
+                status: match &result {
+                    Ok(status) => Some(*status),
+                    Err(e) => e.downcast_ref::<ProviderError>().map(ProviderError::status),
+                },
+                error: result.as_ref().err().map(|e| format!("{:#}", e)),
+                duration_ms: started.elapsed().as_millis() as u64,
+            },
+        ));
+
+        match result {
+            Ok(_) => {
                 info!(
                     "Successfully updated {}.{} to {}",
                     entry.record_name, entry.domain, current_ip
                 );
+                let recovered = state.confirm(current_ip);
+                state.damper.record_update(now);
+
+                let event =
+                    Event::new(EventKind::RecordUpdated, entry).with_ips(old_ip, current_ip);
+                spawn_hooks(
+                    [entry.on_change.as_ref(), settings.hooks.on_change.as_ref()],
+                    &event,
+                );
+                dispatcher.notify(event);
+                if recovered {
+                    dispatcher.notify(
+                        Event::new(EventKind::Recovered, entry).with_ips(old_ip, current_ip),
+                    );
+                }
             }
             Err(e) => {
                 error!(
                     "Failed to update {}.{}: {}",
                     entry.record_name, entry.domain, e
                 );
+                state.failing = true;
+
+                let kind = if ProviderError::is_auth(&e) {
+                    EventKind::AuthFailed
+                } else {
+                    EventKind::UpdateFailed
+                };
+                let event = Event::new(kind, entry)
+                    .with_ips(old_ip, current_ip)
+                    .with_error(format!("{:#}", e));
+                spawn_hooks(
+                    [entry.on_failure.as_ref(), settings.hooks.on_failure.as_ref()],
+                    &event,
+                );
+                dispatcher.notify(event);
             }
         }
     }
 }
 
+/// Run the entry's hook and then the global one in the background
+fn spawn_hooks(hooks: [Option<&CommandSpec>; 2], event: &Event) {
+    let hooks: Vec<CommandSpec> = hooks.into_iter().flatten().cloned().collect();
+    if hooks.is_empty() {
+        return;
+    }
+
+    let event = event.clone();
+    tokio::spawn(async move {
+        for hook in &hooks {
+            if let Err(e) = run_hook(hook, &event).await {
+                warn!("{} hook for {}: {:#}", event.kind, event.entry, e);
+            }
+        }
+    });
+}
+
 async fn wait_for_shutdown() -> Result<()> {
     #[cfg(unix)]
     {
@@ -202,15 +405,23 @@ async fn wait_for_shutdown() -> Result<()> {
 
     #[cfg(windows)]
     {
-        signal::ctrl_c().await?;
+        tokio::signal::ctrl_c().await?;
         info!("Received Ctrl+C");
     }
 
     Ok(())
 }
 
+/// Command line arguments the service runs with
+fn service_args(config: Option<&Path>) -> String {
+    match config {
+        Some(path) => format!("--config \"{}\" daemon", path.display()),
+        None => "daemon".to_string(),
+    }
+}
+
 #[cfg(unix)]
-pub fn install_service() -> Result<()> {
+pub fn install_service(config: Option<&Path>) -> Result<()> {
     use std::process::Command;
 
     let exe_path = std::env::current_exe()?;
@@ -222,14 +433,15 @@ Wants=network-online.target
 
 [Service]
 Type=simple
-ExecStart={}  daemon
+ExecStart={} {}
 Restart=always
 RestartSec=10
 
 [Install]
 WantedBy=multi-user.target
 "#,
-        exe_path.display()
+        exe_path.display(),
+        service_args(config)
     );
 
     let service_path = "/etc/systemd/system/ipdnsd.service";
@@ -273,7 +485,7 @@ pub fn uninstall_service() -> Result<()> {
 }
 
 #[cfg(windows)]
-pub fn install_service() -> Result<()> {
+pub fn install_service(config: Option<&Path>) -> Result<()> {
     use anyhow::anyhow;
 
     // On Windows, we'll use sc.exe for simplicity
@@ -285,7 +497,7 @@ pub fn install_service() -> Result<()> {
             "create",
             "ipdnsd",
             "binPath=",
-            &format!("\"{}\" daemon", exe_path.display()),
+            &format!("\"{}\" {}", exe_path.display(), service_args(config)),
             "start=",
             "auto",
             "DisplayName=",
@@ -327,3 +539,208 @@ pub fn uninstall_service() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{NotificationConfig, NotificationTarget, WebhookConfig};
+    use crate::history::read_history;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Provider holding a single record in memory
+    struct FakeProvider {
+        data: Mutex<String>,
+    }
+
+    #[async_trait]
+    impl DnsProvider for FakeProvider {
+        async fn get_record(
+            &self,
+            _domain: &str,
+            record_type: &str,
+            name: &str,
+        ) -> Result<DnsRecord> {
+            Ok(DnsRecord {
+                name: name.to_string(),
+                record_type: record_type.to_string(),
+                data: self.data.lock().unwrap().clone(),
+                ttl: 600,
+            })
+        }
+
+        async fn update_record(&self, _domain: &str, record: &DnsRecord) -> Result<u16> {
+            *self.data.lock().unwrap() = record.data.clone();
+            Ok(200)
+        }
+
+        async fn list_domains(&self) -> Result<Vec<String>> {
+            Ok(Vec::new())
+        }
+
+        async fn list_records(&self, _domain: &str) -> Result<Vec<DnsRecord>> {
+            Ok(Vec::new())
+        }
+
+        fn provider_name(&self) -> &'static str {
+            "fake"
+        }
+    }
+
+    /// Settings for one entry publishing `ip`, journaling into `dir`
+    fn settings(dir: &Path, ip: &str) -> Settings {
+        let config = dir.join("ipdnsd.toml");
+        std::fs::write(
+            &config,
+            format!(
+                "[history]\npath = \"{}\"\n\n[[dns_entries]]\nprovider = \"godaddy\"\n\
+                 domain = \"example.com\"\nrecord_name = \"www\"\nrecord_type = \"A\"\n\
+                 ip_source = {{ static = \"{}\" }}\n",
+                dir.join("history.jsonl").display(),
+                ip
+            ),
+        )
+        .unwrap();
+        Settings::load_from(&config).unwrap()
+    }
+
+    fn fake_providers(data: &str) -> HashMap<String, Arc<dyn DnsProvider>> {
+        let provider = FakeProvider {
+            data: Mutex::new(data.to_string()),
+        };
+        HashMap::from([("godaddy".to_string(), Arc::new(provider) as Arc<dyn DnsProvider>)])
+    }
+
+    /// Dispatcher posting every event of `kind` to `server`
+    fn webhook_dispatcher(server: &MockServer, kind: EventKind) -> Dispatcher {
+        Dispatcher::new(&[NotificationConfig {
+            events: vec![kind],
+            dedup_seconds: 0,
+            ..NotificationConfig::new(NotificationTarget::Webhook(WebhookConfig {
+                url: server.uri(),
+                method: "POST".to_string(),
+                headers: Default::default(),
+                body: None,
+                content_type: "application/json".to_string(),
+            }))
+        }])
+    }
+
+    #[tokio::test]
+    async fn test_successful_update_journals_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = settings(dir.path(), "198.51.100.7");
+        let journal = Journal::new(&settings.history);
+        let dispatcher = Dispatcher::new(&settings.notifications);
+        let mut states = HashMap::new();
+
+        check_entries(
+            &settings,
+            &fake_providers("198.51.100.1"),
+            &dispatcher,
+            &journal,
+            &mut states,
+        )
+        .await;
+
+        let records = read_history(&settings.history).unwrap();
+        let Some(RecordKind::Update {
+            success, status, ..
+        }) = records.last().map(|r| &r.kind)
+        else {
+            panic!("expected an update, got {:?}", records);
+        };
+        assert!(success);
+        assert_eq!(*status, Some(200));
+    }
+
+    #[tokio::test]
+    async fn test_ip_changed_sent_once_per_accepted_change() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let dispatcher = webhook_dispatcher(&server, EventKind::IpChanged);
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut settings = settings(dir.path(), "198.51.100.7");
+        settings.daemon.damping.max_updates_per_hour = Some(1);
+        let journal = Journal::new(&settings.history);
+        let published = |updated: bool| {
+            let mut state = EntryState {
+                published: Some("198.51.100.1".parse().unwrap()),
+                ..Default::default()
+            };
+            if updated {
+                state.damper.record_update(Instant::now());
+            }
+            HashMap::from([(entry_key(&settings.dns_entries[0]), state)])
+        };
+
+        // Postponed by the update limit, on every check
+        let mut states = published(true);
+        let providers = fake_providers("198.51.100.1");
+        for _ in 0..3 {
+            check_entries(&settings, &providers, &dispatcher, &journal, &mut states).await;
+        }
+
+        // Already in DNS
+        let mut states = published(false);
+        let providers = fake_providers("198.51.100.7");
+        check_entries(&settings, &providers, &dispatcher, &journal, &mut states).await;
+
+        // Published, then unchanged
+        let mut states = published(false);
+        let providers = fake_providers("198.51.100.1");
+        for _ in 0..2 {
+            check_entries(&settings, &providers, &dispatcher, &journal, &mut states).await;
+        }
+
+        // Notifications are sent in the background
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
+    #[tokio::test]
+    async fn test_recovered_when_record_confirmed() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let dispatcher = webhook_dispatcher(&server, EventKind::Recovered);
+
+        let dir = tempfile::tempdir().unwrap();
+        let settings = settings(dir.path(), "198.51.100.7");
+        let journal = Journal::new(&settings.history);
+        let failing = |published: &str| {
+            let state = EntryState {
+                published: Some(published.parse().unwrap()),
+                failing: true,
+                ..Default::default()
+            };
+            HashMap::from([(entry_key(&settings.dns_entries[0]), state)])
+        };
+
+        // The address went back to the published one
+        let mut states = failing("198.51.100.7");
+        let providers = fake_providers("198.51.100.7");
+        check_entries(&settings, &providers, &dispatcher, &journal, &mut states).await;
+
+        // DNS already holds the new address, and a later update isn't a recovery
+        let mut states = failing("198.51.100.1");
+        check_entries(&settings, &providers, &dispatcher, &journal, &mut states).await;
+        for state in states.values_mut() {
+            state.published = Some("198.51.100.1".parse().unwrap());
+        }
+        let providers = fake_providers("198.51.100.1");
+        check_entries(&settings, &providers, &dispatcher, &journal, &mut states).await;
+
+        // Notifications are sent in the background
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use crate::config::{DnsEntry, IpSource};
+
+/// Watch the files used by `file` IP sources and signal `tx` when one changes.
+///
+/// The parent directories are watched rather than the files themselves so that
+/// files replaced by rename, or created after startup, are still noticed.
+/// Paths are canonicalized to match the paths in notify's events.
+/// Returns `None` when no entry uses a file source.
+pub fn watch_ip_files(
+    entries: &[DnsEntry],
+    tx: mpsc::Sender<()>,
+) -> Result<Option<RecommendedWatcher>> {
+    let mut sources = HashSet::new();
+    for entry in entries {
+        collect_files(&entry.ip_source, &mut sources);
+    }
+    let files: HashSet<PathBuf> = sources.iter().flat_map(|path| watched_paths(path)).collect();
+
+    if files.is_empty() {
+        return Ok(None);
+    }
+
+    let watched = files.clone();
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+        if let Ok(event) = result {
+            if event.kind.is_access() {
+                return;
+            }
+            if event.paths.iter().any(|path| watched.contains(path)) {
+                debug!("IP source file changed: {:?}", event.paths);
+                // A full channel already has a check pending
+                let _ = tx.try_send(());
+            }
+        }
+    })
+    .context("Failed to create file watcher")?;
+
+    let dirs: HashSet<PathBuf> = files
+        .iter()
+        .filter_map(|path| path.parent().map(PathBuf::from))
+        .collect();
+    for dir in dirs {
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch directory: {}", dir.display()))?;
+    }
+
+    Ok(Some(watcher))
+}
+
+/// The paths events for `path` can carry: the file in its canonical
+/// directory and, for a symlink, the file it points to
+fn watched_paths(path: &Path) -> Vec<PathBuf> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let dir = parent.canonicalize().unwrap_or_else(|_| parent.to_path_buf());
+
+    let mut paths = Vec::new();
+    if let Some(name) = path.file_name() {
+        paths.push(dir.join(name));
+    }
+    if let Ok(target) = path.canonicalize() {
+        paths.push(target);
+    }
+    paths
+}
+
+fn collect_files(source: &IpSource, files: &mut HashSet<PathBuf>) {
+    match source {
+        IpSource::File(path) => {
+            files.insert(path.clone());
+        }
+        IpSource::Failover(targets) => {
+            for target in targets {
+                collect_files(&target.ip_source, files);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Settings;
+    use std::time::Duration;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_watch_relative_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let conf = dir.path().join("conf");
+        let run = dir.path().join("run");
+        std::fs::create_dir(&conf).unwrap();
+        std::fs::create_dir(&run).unwrap();
+        std::fs::write(run.join("wan-ip"), "198.51.100.23\n").unwrap();
+        std::os::unix::fs::symlink(run.join("wan-ip"), conf.join("wan-ip")).unwrap();
+
+        // A relative path, to a symlink, in the configuration file
+        let config = conf.join("ipdnsd.toml");
+        std::fs::write(
+            &config,
+            "[[dns_entries]]\nprovider = \"godaddy\"\ndomain = \"example.com\"\n\
+             record_name = \"wan\"\nrecord_type = \"A\"\nip_source = { file = \"wan-ip\" }\n",
+        )
+        .unwrap();
+        let settings = Settings::load_from(&config).unwrap();
+        assert_eq!(
+            settings.dns_entries[0].ip_source,
+            IpSource::File(conf.join("wan-ip"))
+        );
+
+        let (tx, mut rx) = mpsc::channel(1);
+        let _watcher = watch_ip_files(&settings.dns_entries, tx).unwrap().unwrap();
+        std::fs::write(run.join("wan-ip"), "198.51.100.24\n").unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("no change noticed")
+            .unwrap();
+    }
+}
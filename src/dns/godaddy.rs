@@ -3,9 +3,10 @@ use std::time::Duration;
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 use reqwest::Client;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use super::provider::{Credentials, DnsProvider, DnsRecord};
+use super::provider::{Credentials, DnsProvider, DnsRecord, ProviderError};
 
 const GODADDY_API_BASE: &str = "https://api.godaddy.com/v1";
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
@@ -13,6 +14,7 @@ const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
 pub struct GoDaddyProvider {
     client: Client,
     credentials: Credentials,
+    base_url: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,12 +31,21 @@ struct GoDaddyRecord {
 
 impl GoDaddyProvider {
     pub fn new(credentials: Credentials) -> Self {
+        Self::with_base_url(credentials, GODADDY_API_BASE)
+    }
+
+    /// Use a different API endpoint, e.g. the OTE test environment
+    pub fn with_base_url(credentials: Credentials, base_url: &str) -> Self {
         let client = Client::builder()
             .timeout(REQUEST_TIMEOUT)
             .build()
             .expect("Failed to create HTTP client");
 
-        Self { client, credentials }
+        Self {
+            client,
+            credentials,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
     }
 
     fn auth_header(&self) -> String {
@@ -43,20 +54,10 @@ impl GoDaddyProvider {
             self.credentials.api_key, self.credentials.api_secret
         )
     }
-}
 
-#[async_trait]
-impl DnsProvider for GoDaddyProvider {
-    async fn get_record(
-        &self,
-        domain: &str,
-        record_type: &str,
-        name: &str,
-    ) -> Result<DnsRecord> {
-        let url = format!(
-            "{}/domains/{}/records/{}/{}",
-            GODADDY_API_BASE, domain, record_type, name
-        );
+    /// GET an API path and parse the JSON response
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let url = format!("{}{}", self.base_url, path);
 
         let response = self
             .client
@@ -67,19 +68,34 @@ impl DnsProvider for GoDaddyProvider {
             .context("Failed to send request to GoDaddy API")?;
 
         if !response.status().is_success() {
-            let status = response.status();
+            let status = response.status().as_u16();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!(
-                "GoDaddy API error ({}): {}",
-                status,
-                body
-            ));
+            return Err(ProviderError::from_status("godaddy", status, body).into());
         }
 
-        let records: Vec<GoDaddyRecord> = response
+        response
             .json()
             .await
-            .context("Failed to parse GoDaddy API response")?;
+            .context("Failed to parse GoDaddy API response")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GoDaddyDomain {
+    domain: String,
+}
+
+#[async_trait]
+impl DnsProvider for GoDaddyProvider {
+    async fn get_record(
+        &self,
+        domain: &str,
+        record_type: &str,
+        name: &str,
+    ) -> Result<DnsRecord> {
+        let records: Vec<GoDaddyRecord> = self
+            .get(&format!("/domains/{}/records/{}/{}", domain, record_type, name))
+            .await?;
 
         let record = records
             .into_iter()
@@ -94,10 +110,10 @@ impl DnsProvider for GoDaddyProvider {
         })
     }
 
-    async fn update_record(&self, domain: &str, record: &DnsRecord) -> Result<()> {
+    async fn update_record(&self, domain: &str, record: &DnsRecord) -> Result<u16> {
         let url = format!(
             "{}/domains/{}/records/{}/{}",
-            GODADDY_API_BASE, domain, record.record_type, record.name
+            self.base_url, domain, record.record_type, record.name
         );
 
         let payload = vec![GoDaddyRecord {
@@ -118,16 +134,32 @@ impl DnsProvider for GoDaddyProvider {
             .context("Failed to send update request to GoDaddy API")?;
 
         if !response.status().is_success() {
-            let status = response.status();
+            let status = response.status().as_u16();
             let body = response.text().await.unwrap_or_default();
-            return Err(anyhow!(
-                "GoDaddy API error ({}): {}",
-                status,
-                body
-            ));
+            return Err(ProviderError::from_status("godaddy", status, body).into());
         }
 
-        Ok(())
+        Ok(response.status().as_u16())
+    }
+
+    async fn list_domains(&self) -> Result<Vec<String>> {
+        let domains: Vec<GoDaddyDomain> = self.get("/domains?statuses=ACTIVE").await?;
+
+        Ok(domains.into_iter().map(|d| d.domain).collect())
+    }
+
+    async fn list_records(&self, domain: &str) -> Result<Vec<DnsRecord>> {
+        let records: Vec<GoDaddyRecord> = self.get(&format!("/domains/{}/records", domain)).await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| DnsRecord {
+                name: record.name.unwrap_or_default(),
+                record_type: record.record_type.unwrap_or_default(),
+                data: record.data,
+                ttl: record.ttl.unwrap_or(600),
+            })
+            .collect())
     }
 
     fn provider_name(&self) -> &'static str {
@@ -149,6 +181,78 @@ mod tests {
         assert_eq!(provider.auth_header(), "sso-key test_key:test_secret");
     }
 
+    #[tokio::test]
+    async fn test_auth_failure_is_classified() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/domains/example.com/records/A/www"))
+            .and(header("Authorization", "sso-key bad_key:bad_secret"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("Unauthorized"))
+            .mount(&server)
+            .await;
+
+        let provider = GoDaddyProvider::with_base_url(
+            Credentials {
+                api_key: "bad_key".to_string(),
+                api_secret: "bad_secret".to_string(),
+            },
+            &server.uri(),
+        );
+
+        let err = provider
+            .get_record("example.com", "A", "www")
+            .await
+            .unwrap_err();
+        assert!(ProviderError::is_auth(&err));
+    }
+
+    #[tokio::test]
+    async fn test_list_domains_and_records() {
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/domains"))
+            .and(query_param("statuses", "ACTIVE"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                {"domain": "example.com", "status": "ACTIVE"},
+                {"domain": "example.org", "status": "ACTIVE"},
+            ])))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/domains/example.com/records"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                {"data": "203.0.113.42", "name": "@", "ttl": 600, "type": "A"},
+                {"data": "mail.example.com", "name": "@", "ttl": 3600, "priority": 10, "type": "MX"},
+            ])))
+            .mount(&server)
+            .await;
+
+        let provider = GoDaddyProvider::with_base_url(
+            Credentials {
+                api_key: "key".to_string(),
+                api_secret: "secret".to_string(),
+            },
+            &server.uri(),
+        );
+
+        assert_eq!(
+            provider.list_domains().await.unwrap(),
+            vec!["example.com", "example.org"]
+        );
+        let records = provider.list_records("example.com").await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].name, "@");
+        assert_eq!(records[0].record_type, "A");
+        assert_eq!(records[0].data, "203.0.113.42");
+        assert_eq!(records[1].record_type, "MX");
+    }
+
     #[test]
     fn test_godaddy_record_serialization() {
         let record = GoDaddyRecord {
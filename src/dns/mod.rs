@@ -2,14 +2,27 @@ mod godaddy;
 mod provider;
 
 pub use godaddy::GoDaddyProvider;
-pub use provider::{Credentials, DnsProvider, DnsRecord};
+pub use provider::{Credentials, DnsProvider, DnsRecord, ProviderError};
 
 use anyhow::{anyhow, Result};
+use std::ops::RangeInclusive;
 use std::sync::Arc;
 
+/// DNS providers that can be used in `dns_entries`
+pub const PROVIDERS: &[&str] = &["godaddy"];
+
 pub fn create_provider(name: &str, credentials: Credentials) -> Result<Arc<dyn DnsProvider>> {
     match name.to_lowercase().as_str() {
         "godaddy" => Ok(Arc::new(GoDaddyProvider::new(credentials))),
         _ => Err(anyhow!("Unknown DNS provider: {}", name)),
     }
 }
+
+/// Record TTLs in seconds accepted by a provider
+pub fn ttl_range(name: &str) -> RangeInclusive<u32> {
+    match name.to_lowercase().as_str() {
+        "godaddy" => 600..=604_800,
+        // RFC 2181: TTLs are 31-bit unsigned values
+        _ => 0..=i32::MAX as u32,
+    }
+}
@@ -3,6 +3,57 @@ use std::net::IpAddr;
 use anyhow::Result;
 use async_trait::async_trait;
 
+/// Errors reported by DNS provider APIs
+#[derive(Debug, thiserror::Error)]
+pub enum ProviderError {
+    /// The provider rejected the credentials
+    #[error("{provider} authentication failed ({status}): {body}")]
+    Auth {
+        provider: &'static str,
+        status: u16,
+        body: String,
+    },
+    #[error("{provider} API error ({status}): {body}")]
+    Api {
+        provider: &'static str,
+        status: u16,
+        body: String,
+    },
+}
+
+impl ProviderError {
+    /// Classify an unsuccessful HTTP response
+    pub fn from_status(provider: &'static str, status: u16, body: String) -> Self {
+        match status {
+            401 | 403 => ProviderError::Auth {
+                provider,
+                status,
+                body,
+            },
+            _ => ProviderError::Api {
+                provider,
+                status,
+                body,
+            },
+        }
+    }
+
+    /// HTTP status returned by the provider
+    pub fn status(&self) -> u16 {
+        match self {
+            ProviderError::Auth { status, .. } | ProviderError::Api { status, .. } => *status,
+        }
+    }
+
+    /// Whether an error chain contains an authentication failure
+    pub fn is_auth(error: &anyhow::Error) -> bool {
+        matches!(
+            error.downcast_ref::<ProviderError>(),
+            Some(ProviderError::Auth { .. })
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Credentials {
     pub api_key: String,
@@ -38,8 +89,14 @@ pub trait DnsProvider: Send + Sync {
         name: &str,
     ) -> Result<DnsRecord>;
 
-    /// Update a DNS record
-    async fn update_record(&self, domain: &str, record: &DnsRecord) -> Result<()>;
+    /// Update a DNS record, returning the HTTP status of the response
+    async fn update_record(&self, domain: &str, record: &DnsRecord) -> Result<u16>;
+
+    /// List the domains managed by the account
+    async fn list_domains(&self) -> Result<Vec<String>>;
+
+    /// List every record of a domain
+    async fn list_records(&self, domain: &str) -> Result<Vec<DnsRecord>>;
 
     /// Get the provider name
     fn provider_name(&self) -> &'static str;
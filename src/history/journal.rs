@@ -0,0 +1,183 @@
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tracing::warn;
+
+use super::record::HistoryRecord;
+use crate::config::HistoryConfig;
+
+/// Append-only JSON Lines journal, rotated by size
+#[derive(Debug, Clone, Default)]
+pub struct Journal {
+    /// `None` when history is disabled
+    path: Option<PathBuf>,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl Journal {
+    pub fn new(config: &HistoryConfig) -> Self {
+        Self {
+            path: config.enabled.then(|| config.path()),
+            max_bytes: config.max_size_kb * 1024,
+            keep: config.keep,
+        }
+    }
+
+    /// Append a record; failures are logged and never stop the daemon
+    pub fn record(&self, record: HistoryRecord) {
+        if let Err(e) = self.append(&record) {
+            warn!("Failed to write history: {:#}", e);
+        }
+    }
+
+    fn append(&self, record: &HistoryRecord) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+        }
+
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open history: {}", path.display()))?;
+        file.write_all(line.as_bytes())
+            .with_context(|| format!("Failed to write history: {}", path.display()))?;
+
+        if file.metadata()?.len() >= self.max_bytes {
+            rotate(path, self.keep)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Path of the `n`th rotated journal, e.g. "history.jsonl.1"
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+/// Shift `path.1` .. `path.{keep-1}` up by one and move `path` to `path.1`
+fn rotate(path: &Path, keep: usize) -> Result<()> {
+    if keep == 0 {
+        return fs::remove_file(path)
+            .with_context(|| format!("Failed to remove history: {}", path.display()));
+    }
+
+    let _ = fs::remove_file(rotated_path(path, keep));
+    for n in (1..keep).rev() {
+        let from = rotated_path(path, n);
+        if from.exists() {
+            fs::rename(&from, rotated_path(path, n + 1))
+                .with_context(|| format!("Failed to rotate history: {}", from.display()))?;
+        }
+    }
+
+    fs::rename(path, rotated_path(path, 1))
+        .with_context(|| format!("Failed to rotate history: {}", path.display()))
+}
+
+/// Read the journal including rotated files, oldest record first
+pub fn read_history(config: &HistoryConfig) -> Result<Vec<HistoryRecord>> {
+    let path = config.path();
+    let mut files: Vec<PathBuf> = (1..=config.keep)
+        .rev()
+        .map(|n| rotated_path(&path, n))
+        .collect();
+    files.push(path);
+
+    let mut records = Vec::new();
+    for file in files.iter().filter(|file| file.exists()) {
+        let reader = BufReader::new(
+            fs::File::open(file)
+                .with_context(|| format!("Failed to open history: {}", file.display()))?,
+        );
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(record) => records.push(record),
+                Err(e) => warn!("Skipping {}:{}: {}", file.display(), number + 1, e),
+            }
+        }
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::RecordKind;
+    use chrono::Utc;
+
+    fn record(n: u8) -> HistoryRecord {
+        HistoryRecord {
+            timestamp: Utc::now(),
+            entry: "www.example.com".to_string(),
+            record_type: "A".to_string(),
+            kind: RecordKind::Observed {
+                old_ip: None,
+                new_ip: format!("198.51.100.{}", n).parse().unwrap(),
+                source: "external".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_rotation_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = HistoryConfig {
+            path: Some(dir.path().join("history.jsonl")),
+            max_size_kb: 1,
+            keep: 2,
+            ..Default::default()
+        };
+        let journal = Journal::new(&config);
+
+        // Each record is ~150 bytes, so this rotates several times
+        for n in 0..30 {
+            journal.record(record(n));
+        }
+
+        assert!(rotated_path(&config.path(), 2).exists());
+        assert!(!rotated_path(&config.path(), 3).exists());
+
+        let records = read_history(&config).unwrap();
+        assert!(!records.is_empty() && records.len() < 30);
+        // Oldest first, ending with the latest record
+        assert!(records
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+        let RecordKind::Observed { new_ip, .. } = &records.last().unwrap().kind else {
+            panic!("expected observation");
+        };
+        assert_eq!(new_ip.to_string(), "198.51.100.29");
+    }
+
+    #[test]
+    fn test_disabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = HistoryConfig {
+            enabled: false,
+            path: Some(dir.path().join("history.jsonl")),
+            ..Default::default()
+        };
+        Journal::new(&config).record(record(1));
+        assert!(!config.path().exists());
+    }
+}
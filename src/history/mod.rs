@@ -0,0 +1,5 @@
+mod journal;
+mod record;
+
+pub use journal::{read_history, Journal};
+pub use record::{format_records, parse_age, HistoryRecord, RecordKind};
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::DnsEntry;
+
+/// One line of the history journal
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoryRecord {
+    pub timestamp: DateTime<Utc>,
+    /// Fully qualified record name, e.g. "www.example.com"
+    pub entry: String,
+    pub record_type: String,
+    #[serde(flatten)]
+    pub kind: RecordKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordKind {
+    /// The IP source returned a different address than on the previous check
+    Observed {
+        old_ip: Option<IpAddr>,
+        new_ip: IpAddr,
+        source: String,
+    },
+    /// A DNS record was written
+    Update {
+        provider: String,
+        old_value: Option<String>,
+        new_value: String,
+        success: bool,
+        /// HTTP status of the provider response, if one was received
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        status: Option<u16>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        duration_ms: u64,
+    },
+}
+
+impl HistoryRecord {
+    pub fn new(entry: &DnsEntry, kind: RecordKind) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            entry: entry.fqdn(),
+            record_type: entry.record_type.clone(),
+            kind,
+        }
+    }
+}
+
+/// Parse an age such as "30m", "12h", "7d" or "2w"
+pub fn parse_age(age: &str) -> Result<Duration> {
+    let age = age.trim();
+    let split = age
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(age.len());
+    let (number, unit) = age.split_at(split);
+    let number: i64 = number
+        .parse()
+        .map_err(|_| anyhow!("Invalid age: {} (expected e.g. 30m, 12h, 7d)", age))?;
+
+    match unit {
+        "s" => Ok(Duration::seconds(number)),
+        "m" => Ok(Duration::minutes(number)),
+        "h" => Ok(Duration::hours(number)),
+        "d" | "" => Ok(Duration::days(number)),
+        "w" => Ok(Duration::weeks(number)),
+        _ => Err(anyhow!("Invalid age unit in {} (use s, m, h, d or w)", age)),
+    }
+}
+
+/// Human readable lines for `ipdnsd history`.
+///
+/// Successful updates show how long after the address change DNS was written.
+pub fn format_records(records: &[HistoryRecord]) -> Vec<String> {
+    let mut changed_at: HashMap<(&str, &str), (IpAddr, DateTime<Utc>)> = HashMap::new();
+
+    records
+        .iter()
+        .map(|record| {
+            let time = record.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true);
+            let key = (record.entry.as_str(), record.record_type.as_str());
+            let prefix = format!("{}  {} ({})", time, record.entry, record.record_type);
+
+            match &record.kind {
+                RecordKind::Observed {
+                    old_ip,
+                    new_ip,
+                    source,
+                } => {
+                    changed_at.insert(key, (*new_ip, record.timestamp));
+                    let old = old_ip
+                        .map(|ip| ip.to_string())
+                        .unwrap_or_else(|| "(none)".to_string());
+                    format!("{}  observed {} -> {} from {}", prefix, old, new_ip, source)
+                }
+                RecordKind::Update {
+                    provider,
+                    old_value,
+                    new_value,
+                    success,
+                    status,
+                    error,
+                    duration_ms,
+                } => {
+                    let old = old_value.as_deref().unwrap_or("(unknown)");
+                    if !success {
+                        let status = status.map(|s| format!(" [{}]", s)).unwrap_or_default();
+                        return format!(
+                            "{}  FAILED   {} -> {} via {}{} in {} ms: {}",
+                            prefix,
+                            old,
+                            new_value,
+                            provider,
+                            status,
+                            duration_ms,
+                            error.as_deref().unwrap_or("unknown error")
+                        );
+                    }
+
+                    let lag = changed_at
+                        .get(&key)
+                        .filter(|(ip, _)| ip.to_string() == *new_value)
+                        .map(|(_, at)| {
+                            format!(", {}s after change", (record.timestamp - *at).num_seconds())
+                        })
+                        .unwrap_or_default();
+                    format!(
+                        "{}  updated  {} -> {} via {} in {} ms{}",
+                        prefix, old, new_value, provider, duration_ms, lag
+                    )
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(seconds: i64, kind: RecordKind) -> HistoryRecord {
+        HistoryRecord {
+            timestamp: DateTime::from_timestamp(1_714_557_600 + seconds, 0).unwrap(),
+            entry: "www.example.com".to_string(),
+            record_type: "A".to_string(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("7d").unwrap(), Duration::days(7));
+        assert_eq!(parse_age("12h").unwrap(), Duration::hours(12));
+        assert_eq!(parse_age("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_age("3").unwrap(), Duration::days(3));
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("5y").is_err());
+    }
+
+    #[test]
+    fn test_serialize() {
+        let line = serde_json::to_string(&record(
+            0,
+            RecordKind::Observed {
+                old_ip: None,
+                new_ip: "198.51.100.2".parse().unwrap(),
+                source: "external".to_string(),
+            },
+        ))
+        .unwrap();
+        assert_eq!(
+            line,
+            r#"{"timestamp":"2024-05-01T10:00:00Z","entry":"www.example.com","record_type":"A","kind":"observed","old_ip":null,"new_ip":"198.51.100.2","source":"external"}"#
+        );
+        let parsed: HistoryRecord = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.timestamp, record(0, parsed.kind.clone()).timestamp);
+    }
+
+    #[test]
+    fn test_format_records_lag() {
+        let lines = format_records(&[
+            record(
+                0,
+                RecordKind::Observed {
+                    old_ip: Some("198.51.100.1".parse().unwrap()),
+                    new_ip: "198.51.100.2".parse().unwrap(),
+                    source: "external".to_string(),
+                },
+            ),
+            record(
+                42,
+                RecordKind::Update {
+                    provider: "godaddy".to_string(),
+                    old_value: Some("198.51.100.1".to_string()),
+                    new_value: "198.51.100.2".to_string(),
+                    success: true,
+                    status: None,
+                    error: None,
+                    duration_ms: 350,
+                },
+            ),
+        ]);
+
+        assert_eq!(
+            lines[0],
+            "2024-05-01T10:00:00Z  www.example.com (A)  observed 198.51.100.1 -> 198.51.100.2 from external"
+        );
+        assert_eq!(
+            lines[1],
+            "2024-05-01T10:00:42Z  www.example.com (A)  updated  198.51.100.1 -> 198.51.100.2 via godaddy in 350 ms, 42s after change"
+        );
+    }
+}
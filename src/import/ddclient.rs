@@ -0,0 +1,383 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::iter::Peekable;
+use std::str::Chars;
+
+use super::{command_source, interface_source, provider_for, HostSettings, Imported};
+use crate::config::{AddressFamily, IpSource};
+use crate::dns::{self, Credentials};
+
+/// Protocol ddclient uses when none is set
+const DEFAULT_PROTOCOL: &str = "dyndns2";
+
+/// Options that are converted into ipdnsd settings
+const MAPPED: &[&str] = &[
+    "cmd", "cmdv4", "cmdv6", "daemon", "if", "ifv4", "ifv6", "ip", "ipv4", "ipv6", "login",
+    "password", "protocol", "ttl", "use", "usev4", "usev6", "web", "webv4", "webv6", "zone",
+];
+
+type Options = BTreeMap<String, String>;
+
+/// Convert a `ddclient.conf`.
+///
+/// Options on a line without host names are defaults for the lines after it;
+/// options on a line with host names apply to those hosts only.
+pub fn parse_ddclient(content: &str) -> Imported {
+    let mut imported = Imported::default();
+    let mut defaults = Options::new();
+    let mut ignored = BTreeSet::new();
+
+    for line in logical_lines(content) {
+        let (options, hosts) = tokenize(&line);
+        ignored.extend(
+            options
+                .keys()
+                .filter(|key| !MAPPED.contains(&key.as_str()))
+                .cloned(),
+        );
+
+        if hosts.is_empty() {
+            defaults.extend(options);
+        } else {
+            let mut merged = defaults.clone();
+            merged.extend(options);
+            add_hosts(&mut imported, &merged, &hosts);
+        }
+    }
+
+    if let Some(daemon) = defaults.get("daemon") {
+        imported.set_interval(&format!("daemon={}", daemon), daemon);
+    }
+    if !ignored.is_empty() {
+        imported.skip(format!(
+            "options without an ipdnsd equivalent: {}",
+            ignored.into_iter().collect::<Vec<_>>().join(", ")
+        ));
+    }
+
+    imported
+}
+
+fn add_hosts(imported: &mut Imported, options: &Options, hosts: &[String]) {
+    let protocol = options
+        .get("protocol")
+        .map(String::as_str)
+        .unwrap_or(DEFAULT_PROTOCOL);
+    let Some(provider) = provider_for(protocol) else {
+        imported.skip(format!(
+            "{}: protocol {} isn't supported (supported: {})",
+            hosts.join(", "),
+            protocol,
+            dns::PROVIDERS.join(", ")
+        ));
+        return;
+    };
+
+    let credentials = match (options.get("login"), options.get("password")) {
+        (Some(login), Some(password)) => imported.add_credentials(
+            provider,
+            Credentials {
+                api_key: login.clone(),
+                api_secret: password.clone(),
+            },
+        ),
+        _ => {
+            imported.skip(format!(
+                "{}: no login and password, store them with `ipdnsd set-key {}`",
+                hosts.join(", "),
+                provider
+            ));
+            None
+        }
+    };
+
+    let ttl = options
+        .get("ttl")
+        .and_then(|ttl| imported.ttl(provider, &format!("ttl={}", ttl), ttl));
+
+    let settings = HostSettings {
+        provider: provider.to_string(),
+        credentials,
+        zone: options.get("zone").cloned(),
+        ttl,
+        sources: address_sources(imported, options),
+    };
+    imported.add_hosts(&settings, hosts);
+}
+
+/// Record types to update and their address sources, from `use`, `usev4`
+/// and `usev6`
+fn address_sources(imported: &mut Imported, options: &Options) -> Vec<(&'static str, IpSource)> {
+    let mut sources = Vec::new();
+    let usev4 = options.get("usev4");
+    let usev6 = options.get("usev6");
+
+    if usev4.is_none() && usev6.is_none() {
+        let method = options.get("use").map(String::as_str).unwrap_or("web");
+        sources.extend(address_source(imported, options, method, "").map(|s| ("A", s)));
+    }
+    if let Some(method) = usev4 {
+        let method = method.strip_suffix("v4").unwrap_or(method);
+        sources.extend(address_source(imported, options, method, "v4").map(|s| ("A", s)));
+    }
+    if let Some(method) = usev6 {
+        let method = method.strip_suffix("v6").unwrap_or(method);
+        sources.extend(address_source(imported, options, method, "v6").map(|s| ("AAAA", s)));
+    }
+
+    sources
+}
+
+fn address_source(
+    imported: &mut Imported,
+    options: &Options,
+    method: &str,
+    suffix: &str,
+) -> Option<IpSource> {
+    let option = |name: &str| options.get(&format!("{}{}", name, suffix));
+
+    match method {
+        "web" => {
+            if let Some(web) = option("web") {
+                imported.skip(format!(
+                    "web{}={}: ipdnsd queries its own external IP services (see [external_ip])",
+                    suffix, web
+                ));
+            }
+            Some(IpSource::External)
+        }
+        "if" => {
+            let family = match suffix {
+                "v4" => AddressFamily::Ipv4,
+                "v6" => AddressFamily::Ipv6,
+                _ => AddressFamily::Any,
+            };
+            Some(interface_source(option("if").cloned(), family))
+        }
+        "ip" => match option("ip").and_then(|ip| ip.parse().ok()) {
+            Some(ip) => Some(IpSource::Static(ip)),
+            None => {
+                imported.skip(format!("use{}=ip without a valid ip{} address", suffix, suffix));
+                None
+            }
+        },
+        "cmd" => match option("cmd") {
+            Some(command) => Some(command_source(command)),
+            None => {
+                imported.skip(format!("use{}=cmd without cmd{}", suffix, suffix));
+                None
+            }
+        },
+        "disabled" | "no" => None,
+        other => {
+            imported.skip(format!(
+                "use{}={}: not supported, the external IP is used instead",
+                suffix, other
+            ));
+            Some(IpSource::External)
+        }
+    }
+}
+
+/// Join continued lines and remove comments and blank lines
+fn logical_lines(content: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+
+    for line in content.lines() {
+        let line = strip_comment(line).trim_end();
+        match line.strip_suffix('\\') {
+            Some(continued) => {
+                current.push_str(continued);
+                current.push(' ');
+            }
+            None => {
+                current.push_str(line);
+                if !current.trim().is_empty() {
+                    lines.push(current.clone());
+                }
+                current.clear();
+            }
+        }
+    }
+    if !current.trim().is_empty() {
+        lines.push(current);
+    }
+
+    lines
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    for (i, c) in line.char_indices() {
+        match (c, quote) {
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('#', None) => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Split a line into `option=value` pairs and host names
+fn tokenize(line: &str) -> (Options, Vec<String>) {
+    let mut options = Options::new();
+    let mut hosts = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    loop {
+        skip_while(&mut chars, |c| c.is_whitespace() || c == ',');
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let word = take_while(&mut chars, |c| !(c.is_whitespace() || c == ',' || c == '='));
+        skip_while(&mut chars, char::is_whitespace);
+        if chars.peek() != Some(&'=') {
+            hosts.push(word);
+            continue;
+        }
+
+        chars.next();
+        skip_while(&mut chars, char::is_whitespace);
+        let value = match chars.peek().copied() {
+            Some(quote @ ('\'' | '"')) => {
+                chars.next();
+                let value = take_while(&mut chars, |c| c != quote);
+                chars.next();
+                value
+            }
+            _ => take_while(&mut chars, |c| !(c.is_whitespace() || c == ',')),
+        };
+        options.insert(word.to_lowercase(), value);
+    }
+
+    (options, hosts)
+}
+
+fn skip_while(chars: &mut Peekable<Chars>, pred: impl Fn(char) -> bool) {
+    while chars.next_if(|&c| pred(c)).is_some() {}
+}
+
+fn take_while(chars: &mut Peekable<Chars>, pred: impl Fn(char) -> bool) -> String {
+    let mut taken = String::new();
+    while let Some(c) = chars.next_if(|&c| pred(c)) {
+        taken.push(c);
+    }
+    taken
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{validate, DnsEntry, InternalSelector};
+    use crate::import::Format;
+    use crate::ip::check_address;
+
+    const CONFIG: &str = r#"
+# ddclient.conf
+daemon=5m
+syslog=yes
+ssl=yes
+use=web, web=checkip.dyndns.org/, web-skip='IP Address'
+
+protocol=godaddy, \
+login=key1, \
+password='se#cret', \
+zone=example.com, ttl = 600 \
+home.example.com,example.com
+
+protocol=godaddy, login=key1, password='se#cret', zone=example.com, \
+usev4=ifv4, ifv4=eth1, usev6=cmdv6, cmdv6="ip -6 addr show dev eth1 | awk '/global/ {print $2}'" \
+lan.example.com
+
+protocol=dyndns2, login=user, password=pass members.example.net
+"#;
+
+    #[test]
+    fn test_parse_ddclient() {
+        let imported = parse_ddclient(CONFIG);
+
+        assert_eq!(imported.interval_seconds, Some(300));
+        assert_eq!(imported.credentials.len(), 1);
+        assert_eq!(imported.credentials[0].0, "godaddy");
+        assert_eq!(imported.credentials[0].2.api_secret, "se#cret");
+
+        let entries: Vec<String> = imported
+            .entries
+            .iter()
+            .map(|e| format!("{} {} {} {} {:?}", e.domain, e.record_name, e.record_type, e.ip_source, e.ttl))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                "example.com home A external Some(600)",
+                "example.com @ A external Some(600)",
+                "example.com lan A internal None",
+                "example.com lan AAAA command None",
+            ]
+        );
+        assert_eq!(
+            imported.entries[2].ip_source,
+            IpSource::Internal(InternalSelector {
+                interface: Some("eth1".to_string()),
+                family: AddressFamily::Ipv4,
+                ..Default::default()
+            })
+        );
+
+        assert_eq!(
+            imported.skipped,
+            [
+                "web=checkip.dyndns.org/: ipdnsd queries its own external IP services (see [external_ip])",
+                "members.example.net: protocol dyndns2 isn't supported (supported: godaddy)",
+                "options without an ipdnsd equivalent: ssl, syslog, web-skip",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_out_of_range_values_are_clamped() {
+        let imported = parse_ddclient(
+            "daemon=5\nprotocol=godaddy, login=key, password=secret, ttl=300 example.com\n",
+        );
+
+        assert_eq!(imported.interval_seconds, Some(10));
+        assert_eq!(imported.entries[0].ttl, Some(600));
+        assert_eq!(
+            imported.skipped,
+            [
+                "ttl=300: godaddy accepts TTLs of 600 to 604800 seconds, using 600",
+                "daemon=5: below the minimum interval, using 10 seconds",
+            ]
+        );
+        let rendered = imported.render(Format::Ddclient, "ddclient.conf".as_ref());
+        assert!(validate(&rendered, None).is_ok());
+    }
+
+    #[test]
+    fn test_interface_sources_publish_public_addresses() {
+        let imported = parse_ddclient(
+            "protocol=godaddy, login=key, password=secret, usev4=ifv4, ifv4=ppp0, \
+             usev6=ifv6, ifv6=ppp0 example.com\n",
+        );
+        let rendered = imported.render(Format::Ddclient, "ddclient.conf".as_ref());
+        assert!(rendered.contains("policy = { scope = \"public\" }"));
+
+        let settings = validate(&rendered, None).unwrap();
+        let [v4, v6] = &settings.dns_entries[..] else {
+            panic!("expected an A and an AAAA entry");
+        };
+        let IpSource::Internal(selector) = &v6.ip_source else {
+            panic!("expected an interface source");
+        };
+        assert_eq!(selector.family, AddressFamily::Ipv6);
+
+        let check = |entry: &DnsEntry, ip: &str| {
+            check_address(entry, &ip.parse().unwrap(), &entry.ip_source).is_ok()
+        };
+        assert!(check(v4, "8.8.8.8"));
+        assert!(!check(v4, "192.168.1.10"));
+        assert!(check(v6, "2606:4700:4700::1111"));
+    }
+}
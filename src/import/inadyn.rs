@@ -0,0 +1,399 @@
+use std::collections::BTreeSet;
+
+use anyhow::{bail, Result};
+
+use super::{command_source, interface_source, provider_for, HostSettings, Imported};
+use crate::config::{AddressFamily, IpSource};
+use crate::dns::{self, Credentials};
+
+/// Settings inside a `provider` section that are converted
+const MAPPED: &[&str] = &[
+    "username",
+    "password",
+    "hostname",
+    "ttl",
+    "checkip-command",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Open,
+    Close,
+    Equals,
+    Comma,
+}
+
+#[derive(Debug)]
+enum Node {
+    Option(String, Vec<String>),
+    Section {
+        name: String,
+        title: Option<String>,
+        children: Vec<Node>,
+    },
+}
+
+/// Convert an `inadyn.conf` (inadyn 2.x syntax)
+pub fn parse_inadyn(content: &str) -> Result<Imported> {
+    let tokens = tokenize(content)?;
+    let mut tokens = tokens.into_iter().peekable();
+    let nodes = parse_nodes(&mut tokens, false)?;
+
+    let mut imported = Imported::default();
+    let mut interface = None;
+    let mut ignored = BTreeSet::new();
+
+    for node in &nodes {
+        if let Node::Option(key, values) = node {
+            match key.as_str() {
+                "period" => {
+                    let value = values.join(", ");
+                    imported.set_interval(&format!("period = {}", value), &value);
+                }
+                "iface" => interface = values.first().cloned(),
+                key => {
+                    ignored.insert(key.to_string());
+                }
+            }
+        }
+    }
+
+    for node in &nodes {
+        let Node::Section {
+            name,
+            title,
+            children,
+        } = node
+        else {
+            continue;
+        };
+        let title = title.clone().unwrap_or_default();
+        match name.as_str() {
+            "provider" => add_provider(&mut imported, &title, children, interface.as_deref(), &mut ignored),
+            "custom" => imported.skip(format!(
+                "custom {}: custom providers aren't supported (supported: {})",
+                title,
+                dns::PROVIDERS.join(", ")
+            )),
+            other => imported.skip(format!("section {} {}: not supported", other, title)),
+        }
+    }
+
+    if !ignored.is_empty() {
+        imported.skip(format!(
+            "settings without an ipdnsd equivalent: {}",
+            ignored.into_iter().collect::<Vec<_>>().join(", ")
+        ));
+    }
+
+    Ok(imported)
+}
+
+fn add_provider(
+    imported: &mut Imported,
+    title: &str,
+    children: &[Node],
+    interface: Option<&str>,
+    ignored: &mut BTreeSet<String>,
+) {
+    let option = |key: &str| {
+        children.iter().find_map(|node| match node {
+            Node::Option(name, values) if name == key => Some(values.clone()),
+            _ => None,
+        })
+    };
+    let hosts = option("hostname").unwrap_or_default();
+
+    let Some(provider) = provider_for(title) else {
+        imported.skip(format!(
+            "provider {}: not supported (supported: {})",
+            title,
+            dns::PROVIDERS.join(", ")
+        ));
+        return;
+    };
+    if hosts.is_empty() {
+        imported.skip(format!("provider {}: no hostname", title));
+        return;
+    }
+
+    let first = |key: &str| option(key).and_then(|values| values.into_iter().next());
+    let credentials = match (first("username"), first("password")) {
+        (Some(api_key), Some(api_secret)) => imported.add_credentials(
+            provider,
+            Credentials {
+                api_key,
+                api_secret,
+            },
+        ),
+        _ => {
+            imported.skip(format!(
+                "provider {}: no username and password, store them with `ipdnsd set-key {}`",
+                title, provider
+            ));
+            None
+        }
+    };
+
+    let ttl =
+        first("ttl").and_then(|ttl| imported.ttl(provider, &format!("ttl = {}", ttl), &ttl));
+
+    let checkip_command = first("checkip-command");
+    let source = |family| match (&checkip_command, interface) {
+        (Some(command), _) => command_source(command),
+        (None, Some(interface)) => interface_source(Some(interface.to_string()), family),
+        (None, None) => IpSource::External,
+    };
+    let mut sources = vec![("A", source(AddressFamily::Ipv4))];
+    if first("allow-ipv6").as_deref() == Some("true") || title.starts_with("ipv6@") {
+        sources.push(("AAAA", source(AddressFamily::Ipv6)));
+    }
+
+    for node in children {
+        match node {
+            Node::Option(key, _) if !MAPPED.contains(&key.as_str()) && key != "allow-ipv6" => {
+                ignored.insert(key.clone());
+            }
+            Node::Section { name, .. } => {
+                imported.skip(format!("provider {}: section {} not supported", title, name))
+            }
+            _ => {}
+        }
+    }
+
+    let settings = HostSettings {
+        provider: provider.to_string(),
+        credentials,
+        zone: None,
+        ttl,
+        sources,
+    };
+    imported.add_hosts(&settings, &hosts);
+}
+
+fn parse_nodes(
+    tokens: &mut std::iter::Peekable<std::vec::IntoIter<Token>>,
+    nested: bool,
+) -> Result<Vec<Node>> {
+    let mut nodes = Vec::new();
+
+    loop {
+        let name = match tokens.next() {
+            None if nested => bail!("missing `}}`"),
+            None => return Ok(nodes),
+            Some(Token::Close) if nested => return Ok(nodes),
+            Some(Token::Word(name)) => name.to_lowercase(),
+            Some(token) => bail!("unexpected {:?}", token),
+        };
+
+        match tokens.next() {
+            Some(Token::Equals) => nodes.push(Node::Option(name, parse_values(tokens)?)),
+            Some(Token::Open) => nodes.push(Node::Section {
+                name,
+                title: None,
+                children: parse_nodes(tokens, true)?,
+            }),
+            Some(Token::Word(title)) => match tokens.next() {
+                Some(Token::Open) => nodes.push(Node::Section {
+                    name,
+                    title: Some(title),
+                    children: parse_nodes(tokens, true)?,
+                }),
+                _ => bail!("expected `{{` after {} {}", name, title),
+            },
+            _ => bail!("expected `=` or `{{` after {}", name),
+        }
+    }
+}
+
+/// A value or a `{ a, b }` list
+fn parse_values(tokens: &mut std::iter::Peekable<std::vec::IntoIter<Token>>) -> Result<Vec<String>> {
+    match tokens.next() {
+        Some(Token::Word(value)) => Ok(vec![value]),
+        Some(Token::Open) => {
+            let mut values = Vec::new();
+            loop {
+                match tokens.next() {
+                    Some(Token::Word(value)) => values.push(value),
+                    Some(Token::Comma) => {}
+                    Some(Token::Close) => return Ok(values),
+                    _ => bail!("unterminated list"),
+                }
+            }
+        }
+        _ => bail!("expected a value after `=`"),
+    }
+}
+
+fn tokenize(content: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '{' => tokens.push(Token::Open),
+            '}' => tokens.push(Token::Close),
+            '=' => tokens.push(Token::Equals),
+            ',' => tokens.push(Token::Comma),
+            '#' => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if previous == '*' => break,
+                        Some(c) => previous = c,
+                        None => bail!("unterminated comment"),
+                    }
+                }
+            }
+            '"' | '\'' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') if c == '"' => value.extend(chars.next()),
+                        Some(q) if q == c => break,
+                        Some(other) => value.push(other),
+                        None => bail!("unterminated string"),
+                    }
+                }
+                tokens.push(Token::Word(value));
+            }
+            c => {
+                let mut word = c.to_string();
+                while let Some(c) =
+                    chars.next_if(|&c| !c.is_whitespace() && !"{}=,#\"'".contains(c))
+                {
+                    word.push(c);
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{validate, AddressScope, InternalSelector};
+    use crate::import::Format;
+
+    const CONFIG: &str = r#"
+# inadyn.conf
+period          = 600
+user-agent      = "Mozilla/5.0"
+iface           = eth0
+
+/* GoDaddy account */
+provider godaddy.com {
+    username    = key1
+    password    = "se\"cret"
+    hostname    = { "home.example.com", "example.com" }
+    ttl         = 3600
+    checkip-ssl = false
+}
+
+provider default@dyndns.org {
+    username = user
+    password = pass
+    hostname = host.dyndns.org
+}
+
+custom example {
+    ddns-server = "update.example.net"
+    hostname    = "box.example.net"
+}
+"#;
+
+    #[test]
+    fn test_parse_inadyn() {
+        let imported = parse_inadyn(CONFIG).unwrap();
+
+        assert_eq!(imported.interval_seconds, Some(600));
+        assert_eq!(imported.credentials.len(), 1);
+        assert_eq!(imported.credentials[0].2.api_secret, "se\"cret");
+
+        let entries: Vec<String> = imported
+            .entries
+            .iter()
+            .map(|e| format!("{} {} {} {:?}", e.domain, e.record_name, e.record_type, e.ttl))
+            .collect();
+        assert_eq!(entries, ["example.com home A Some(3600)", "example.com @ A Some(3600)"]);
+        assert_eq!(
+            imported.entries[0].ip_source,
+            IpSource::Internal(InternalSelector {
+                interface: Some("eth0".to_string()),
+                family: AddressFamily::Ipv4,
+                ..Default::default()
+            })
+        );
+
+        assert_eq!(
+            imported.skipped,
+            [
+                "host home.example.com: no zone given, assumed the domain is example.com",
+                "provider default@dyndns.org: not supported (supported: godaddy)",
+                "custom example: custom providers aren't supported (supported: godaddy)",
+                "settings without an ipdnsd equivalent: checkip-ssl, user-agent",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_inadyn_syntax_error() {
+        assert!(parse_inadyn("provider godaddy.com {\n username = key\n").is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_values_are_clamped() {
+        let imported = parse_inadyn(
+            "period = 5\nprovider godaddy.com {\n username = key\n password = secret\n \
+             hostname = example.com\n ttl = 300\n}\n",
+        )
+        .unwrap();
+
+        assert_eq!(imported.interval_seconds, Some(10));
+        assert_eq!(imported.entries[0].ttl, Some(600));
+        assert_eq!(
+            imported.skipped,
+            [
+                "period = 5: below the minimum interval, using 10 seconds",
+                "ttl = 300: godaddy accepts TTLs of 600 to 604800 seconds, using 600",
+            ]
+        );
+        let rendered = imported.render(Format::Inadyn, "inadyn.conf".as_ref());
+        assert!(validate(&rendered, None).is_ok());
+    }
+
+    #[test]
+    fn test_ipv6_interface_source() {
+        let imported = parse_inadyn(
+            "iface = ppp0\nprovider godaddy.com {\n username = key\n password = secret\n \
+             hostname = example.com\n allow-ipv6 = true\n}\n",
+        )
+        .unwrap();
+
+        let families: Vec<_> = imported
+            .entries
+            .iter()
+            .map(|entry| match &entry.ip_source {
+                IpSource::Internal(selector) => (entry.record_type.as_str(), selector.family),
+                other => panic!("expected an interface source, got {}", other),
+            })
+            .collect();
+        assert_eq!(families, [("A", AddressFamily::Ipv4), ("AAAA", AddressFamily::Ipv6)]);
+        assert!(imported
+            .entries
+            .iter()
+            .all(|entry| entry.policy.scope == Some(AddressScope::Public)));
+    }
+}
@@ -0,0 +1,443 @@
+mod ddclient;
+mod inadyn;
+
+pub use ddclient::parse_ddclient;
+pub use inadyn::parse_inadyn;
+
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::config::{
+    validate, AddressFamily, AddressPolicy, AddressScope, CommandSpec, InternalSelector, IpSource,
+    MIN_INTERVAL_SECONDS,
+};
+use crate::dns::{self, Credentials};
+use crate::init::{render_config, write_config, InitConfig, InitEntry};
+use crate::secrets::{self, CredentialStore};
+
+/// Interval used when the imported configuration doesn't set one
+const DEFAULT_INTERVAL_SECONDS: u64 = 300;
+
+/// Dynamic DNS clients whose configuration can be imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Ddclient,
+    Inadyn,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Ddclient => write!(f, "ddclient"),
+            Format::Inadyn => write!(f, "inadyn"),
+        }
+    }
+}
+
+/// A configuration converted from another client
+#[derive(Debug, Default)]
+pub struct Imported {
+    pub interval_seconds: Option<u64>,
+    pub entries: Vec<InitEntry>,
+    /// Credentials by provider and credential profile
+    pub credentials: Vec<(String, Option<String>, Credentials)>,
+    /// Settings that couldn't be mapped or were adjusted
+    pub skipped: Vec<String>,
+}
+
+impl Imported {
+    pub(crate) fn skip(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        if !self.skipped.contains(&message) {
+            self.skipped.push(message);
+        }
+    }
+
+    /// Set the check interval from `setting`, e.g. `daemon=5m`. Intervals
+    /// below ipdnsd's minimum are raised to it.
+    pub(crate) fn set_interval(&mut self, setting: &str, value: &str) {
+        match parse_interval(value) {
+            Some(seconds) if seconds < MIN_INTERVAL_SECONDS => {
+                self.skip(format!(
+                    "{}: below the minimum interval, using {} seconds",
+                    setting, MIN_INTERVAL_SECONDS
+                ));
+                self.interval_seconds = Some(MIN_INTERVAL_SECONDS);
+            }
+            Some(seconds) => self.interval_seconds = Some(seconds),
+            None => self.skip(format!("{}: not an interval", setting)),
+        }
+    }
+
+    /// The TTL from `setting`, e.g. `ttl=300`, clamped to what `provider`
+    /// accepts
+    pub(crate) fn ttl(&mut self, provider: &str, setting: &str, value: &str) -> Option<u32> {
+        let Ok(ttl) = value.trim().parse::<u32>() else {
+            self.skip(format!("{}: not a number of seconds", setting));
+            return None;
+        };
+
+        let range = dns::ttl_range(provider);
+        if range.contains(&ttl) {
+            return Some(ttl);
+        }
+        let clamped = ttl.clamp(*range.start(), *range.end());
+        self.skip(format!(
+            "{}: {} accepts TTLs of {} to {} seconds, using {}",
+            setting,
+            provider,
+            range.start(),
+            range.end(),
+            clamped
+        ));
+        Some(clamped)
+    }
+
+    /// Add an account's credentials, returning the credential profile its
+    /// hosts use. The first account of a provider becomes its default
+    /// credentials, further ones profiles named `account-2`, `account-3`...
+    pub(crate) fn add_credentials(&mut self, provider: &str, credentials: Credentials) -> Option<String> {
+        let accounts: Vec<_> = self
+            .credentials
+            .iter()
+            .filter(|(name, _, _)| name == provider)
+            .collect();
+        if let Some((_, profile, _)) = accounts.iter().find(|(_, _, existing)| {
+            existing.api_key == credentials.api_key && existing.api_secret == credentials.api_secret
+        }) {
+            return profile.clone();
+        }
+
+        let profile = match accounts.len() {
+            0 => None,
+            n => Some(format!("account-{}", n + 1)),
+        };
+        self.credentials
+            .push((provider.to_string(), profile.clone(), credentials));
+        profile
+    }
+
+    /// Add entries for the host names of one account
+    pub(crate) fn add_hosts(&mut self, host: &HostSettings, hosts: &[String]) {
+        for name in hosts {
+            let Some((domain, record_name)) = self.split_host(name, host.zone.as_deref()) else {
+                continue;
+            };
+            for (record_type, ip_source) in &host.sources {
+                // Interfaces the other clients read are WAN interfaces, whose
+                // addresses are public unlike the LAN addresses an internal
+                // source publishes by default
+                let policy = match ip_source {
+                    IpSource::Internal(_) => AddressPolicy {
+                        scope: Some(AddressScope::Public),
+                        ..Default::default()
+                    },
+                    _ => AddressPolicy::default(),
+                };
+                self.entries.push(InitEntry {
+                    provider: host.provider.clone(),
+                    credentials: host.credentials.clone(),
+                    domain: domain.clone(),
+                    record_name: record_name.clone(),
+                    record_type: record_type.to_string(),
+                    ip_source: ip_source.clone(),
+                    ttl: host.ttl,
+                    policy,
+                    note: Some(format!("Imported host {}", name)),
+                });
+            }
+        }
+    }
+
+    /// Split a host name into domain and record name. Without a zone, the
+    /// domain is assumed to be the last two labels.
+    fn split_host(&mut self, host: &str, zone: Option<&str>) -> Option<(String, String)> {
+        let host = host.trim_end_matches('.').to_lowercase();
+        let zone = match zone {
+            Some(zone) => zone.trim_end_matches('.').to_lowercase(),
+            None => {
+                let labels: Vec<&str> = host.split('.').collect();
+                if labels.len() < 2 {
+                    self.skip(format!("host {}: not a fully qualified name", host));
+                    return None;
+                }
+                let zone = labels[labels.len() - 2..].join(".");
+                if labels.len() > 2 {
+                    self.skip(format!(
+                        "host {}: no zone given, assumed the domain is {}",
+                        host, zone
+                    ));
+                }
+                zone
+            }
+        };
+
+        if host == zone {
+            Some((zone, "@".to_string()))
+        } else if let Some(name) = host.strip_suffix(&format!(".{}", zone)) {
+            Some((zone, name.to_string()))
+        } else {
+            self.skip(format!("host {}: not in zone {}", host, zone));
+            None
+        }
+    }
+
+    /// Render the converted configuration, listing what was skipped
+    pub fn render(&self, format: Format, path: &Path) -> String {
+        let mut comment = vec![format!(
+            "Imported from the {} configuration {}.",
+            format,
+            path.display()
+        )];
+        if !self.skipped.is_empty() {
+            comment.push(String::new());
+            comment.push("Not imported or adjusted:".to_string());
+            comment.extend(self.skipped.iter().map(|skipped| format!("  - {}", skipped)));
+        }
+
+        render_config(&InitConfig {
+            comment,
+            interval_seconds: self.interval_seconds.unwrap_or(DEFAULT_INTERVAL_SECONDS),
+            entries: self.entries.clone(),
+        })
+    }
+}
+
+/// Provider, credentials and address sources shared by a group of hosts
+#[derive(Debug, Clone)]
+pub(crate) struct HostSettings {
+    pub provider: String,
+    /// Credential profile of the account
+    pub credentials: Option<String>,
+    pub zone: Option<String>,
+    pub ttl: Option<u32>,
+    /// Record types to update and where their addresses come from
+    pub sources: Vec<(&'static str, IpSource)>,
+}
+
+/// The ipdnsd provider for another client's protocol or provider name
+pub(crate) fn provider_for(name: &str) -> Option<&'static str> {
+    let name = name.to_lowercase();
+    let name = name.strip_prefix("default@").unwrap_or(&name);
+    dns::PROVIDERS
+        .iter()
+        .copied()
+        .find(|provider| name == *provider || name.starts_with(&format!("{}.", provider)))
+}
+
+/// The address of `interface` in `family` as an address source
+pub(crate) fn interface_source(interface: Option<String>, family: AddressFamily) -> IpSource {
+    IpSource::Internal(InternalSelector {
+        interface,
+        family,
+        ..Default::default()
+    })
+}
+
+/// A shell command as an address source
+pub(crate) fn command_source(command: &str) -> IpSource {
+    IpSource::Command(CommandSpec {
+        args: vec!["/bin/sh".to_string(), "-c".to_string(), command.to_string()],
+        timeout_seconds: None,
+    })
+}
+
+/// Seconds, optionally with an s, m, h or d suffix. None if the value isn't
+/// an interval or doesn't fit in a u64 of seconds.
+pub(crate) fn parse_interval(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().ok()?;
+
+    let multiplier = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        _ => return None,
+    };
+    number.checked_mul(multiplier)
+}
+
+/// Convert another client's configuration file and write it to `config_path`
+/// together with its credentials, or only print it with `dry_run`
+pub fn run(
+    format: Format,
+    path: &Path,
+    config_path: &Path,
+    store: &dyn CredentialStore,
+    dry_run: bool,
+    force: bool,
+) -> Result<()> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let imported = match format {
+        Format::Ddclient => parse_ddclient(&content),
+        Format::Inadyn => parse_inadyn(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?,
+    };
+    if imported.entries.is_empty() {
+        bail!(
+            "No hosts could be imported from {}:\n  {}",
+            path.display(),
+            imported.skipped.join("\n  ")
+        );
+    }
+
+    let rendered = imported.render(format, path);
+    validate(&rendered, None).map_err(|diagnostics| {
+        let problems: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+        anyhow!("Converted configuration is invalid:\n  {}", problems.join("\n  "))
+    })?;
+
+    if dry_run {
+        print!("{}", rendered);
+        for (provider, profile, _) in &imported.credentials {
+            println!(
+                "# Credentials found for provider: {}",
+                secrets::describe(provider, profile.as_deref())
+            );
+        }
+        return Ok(());
+    }
+
+    if config_path.exists() && !force {
+        bail!(
+            "{} already exists. Use --force to overwrite it or --config to choose another file.",
+            config_path.display()
+        );
+    }
+    if !force {
+        let mut stored = Vec::new();
+        for (provider, profile, _) in &imported.credentials {
+            let profile = profile.as_deref();
+            if store.get(provider, profile)?.is_some() {
+                stored.push(secrets::describe(provider, profile));
+            }
+        }
+        if !stored.is_empty() {
+            bail!(
+                "Credentials for {} are already stored in {}. Use --force to replace them.",
+                stored.join(", "),
+                store.location()
+            );
+        }
+    }
+
+    // The configuration is only written once its credentials are in place
+    for (provider, profile, credentials) in &imported.credentials {
+        let profile = profile.as_deref();
+        store.store(provider, profile, credentials)?;
+        println!(
+            "Credentials stored for provider {} in {}",
+            secrets::describe(provider, profile),
+            store.location()
+        );
+    }
+
+    write_config(config_path, &rendered)?;
+    println!(
+        "Imported {} DNS entries into {}",
+        imported.entries.len(),
+        config_path.display()
+    );
+
+    if !imported.skipped.is_empty() {
+        println!("\nNot imported or adjusted:");
+        for skipped in &imported.skipped {
+            println!("  - {}", skipped);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::FileStore;
+
+    #[test]
+    fn test_split_host() {
+        let mut imported = Imported::default();
+        let split = |imported: &mut Imported, host, zone| imported.split_host(host, zone);
+
+        assert_eq!(
+            split(&mut imported, "home.example.com", Some("example.com")),
+            Some(("example.com".to_string(), "home".to_string()))
+        );
+        assert_eq!(
+            split(&mut imported, "Example.com.", None),
+            Some(("example.com".to_string(), "@".to_string()))
+        );
+        assert_eq!(split(&mut imported, "home.example.org", Some("example.com")), None);
+        assert_eq!(
+            imported.skipped,
+            ["host home.example.org: not in zone example.com"]
+        );
+    }
+
+    #[test]
+    fn test_add_credentials() {
+        let mut imported = Imported::default();
+        let account = |key: &str| Credentials {
+            api_key: key.to_string(),
+            api_secret: "secret".to_string(),
+        };
+
+        assert_eq!(imported.add_credentials("godaddy", account("a")), None);
+        assert_eq!(
+            imported.add_credentials("godaddy", account("b")),
+            Some("account-2".to_string())
+        );
+        assert_eq!(imported.add_credentials("godaddy", account("a")), None);
+        assert_eq!(
+            imported.add_credentials("godaddy", account("b")),
+            Some("account-2".to_string())
+        );
+        assert_eq!(imported.credentials.len(), 2);
+    }
+
+    #[test]
+    fn test_run_keeps_stored_credentials() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("ddclient.conf");
+        let config = dir.path().join("config.toml");
+        std::fs::write(&source, "protocol=godaddy, login=new, password=secret example.com\n")
+            .unwrap();
+        let store = FileStore::new(dir.path().join("credentials.toml"));
+        let old = Credentials {
+            api_key: "old".to_string(),
+            api_secret: "secret".to_string(),
+        };
+        store.store("godaddy", None, &old).unwrap();
+
+        let error = run(Format::Ddclient, &source, &config, &store, false, false).unwrap_err();
+        assert!(error.to_string().starts_with("Credentials for godaddy are already stored"));
+        assert_eq!(store.credentials("godaddy", None).unwrap().api_key, "old");
+        assert!(!config.exists());
+
+        run(Format::Ddclient, &source, &config, &store, false, true).unwrap();
+        assert_eq!(store.credentials("godaddy", None).unwrap().api_key, "new");
+        assert!(config.exists());
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("300"), Some(300));
+        assert_eq!(parse_interval("5m"), Some(300));
+        assert_eq!(parse_interval("1h"), Some(3600));
+        assert_eq!(parse_interval("soon"), None);
+        assert_eq!(parse_interval("999999999999999999d"), None);
+
+        let mut imported = Imported::default();
+        imported.set_interval("daemon=999999999999999999d", "999999999999999999d");
+        assert_eq!(imported.interval_seconds, None);
+        assert_eq!(imported.skipped, ["daemon=999999999999999999d: not an interval"]);
+    }
+}
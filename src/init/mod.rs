@@ -0,0 +1,325 @@
+mod prompt;
+mod template;
+
+pub use prompt::Prompt;
+pub use template::{render_config, InitConfig, InitEntry};
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::config::{validate, AddressFamily, AddressPolicy, IpSource, MIN_INTERVAL_SECONDS};
+use crate::dns::{self, create_provider, Credentials, DnsProvider, ProviderError};
+use crate::ip;
+use crate::secrets::CredentialStore;
+
+/// Attempts at entering credentials the provider accepts
+const CREDENTIAL_ATTEMPTS: usize = 3;
+
+/// Interactively create a configuration file and store credentials
+pub async fn run(config_path: &Path, store: &dyn CredentialStore) -> Result<()> {
+    let stdin = std::io::stdin();
+    let mut prompt = Prompt::new(stdin.lock(), std::io::stdout());
+
+    prompt.say("Detecting IP addresses...")?;
+    let external = ip::get_external_ip(&Default::default(), AddressFamily::Any).await;
+    let internal = ip::get_internal_ip(&Default::default(), AddressFamily::Any);
+    match &external {
+        Ok(ip) => prompt.say(format!("  External IP: {}", ip))?,
+        Err(e) => prompt.say(format!("  External IP: not detected ({})", e))?,
+    }
+    match &internal {
+        Ok(ip) => prompt.say(format!("  Internal IP: {}", ip))?,
+        Err(e) => prompt.say(format!("  Internal IP: not detected ({})", e))?,
+    }
+    prompt.say("")?;
+
+    let providers: Vec<String> = dns::PROVIDERS.iter().map(|p| p.to_string()).collect();
+    let provider_name = providers[prompt.choose("DNS provider:", &providers, 0)?].clone();
+    let (provider, credentials, domains) = connect(&mut prompt, &provider_name, store).await?;
+
+    let mut entries = Vec::new();
+    loop {
+        prompt.say("")?;
+        let domain = domains[prompt.choose("Domain:", &domains, 0)?].clone();
+        let detected = Detected {
+            external: external.as_ref().ok().copied(),
+            internal: internal.as_ref().ok().copied(),
+        };
+        entries.extend(pick_records(&mut prompt, provider.as_ref(), &domain, &detected).await?);
+
+        if !prompt.confirm("Add records from another domain?", false)? {
+            break;
+        }
+    }
+
+    prompt.say("")?;
+    let interval_seconds = loop {
+        let answer = prompt.ask("Check interval in seconds", Some("300"))?;
+        match answer.parse::<u64>() {
+            Ok(seconds) if seconds >= MIN_INTERVAL_SECONDS => break seconds,
+            _ => prompt.say(format!(
+                "Enter a number of seconds, at least {}.",
+                MIN_INTERVAL_SECONDS
+            ))?,
+        }
+    };
+
+    let rendered = render_config(&InitConfig {
+        comment: vec!["Written by `ipdnsd init`.".to_string()],
+        interval_seconds,
+        entries,
+    });
+    validate(&rendered, None).map_err(|diagnostics| {
+        let problems: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+        anyhow!("Generated configuration is invalid: {}", problems.join("; "))
+    })?;
+
+    if config_path.exists()
+        && !prompt.confirm(
+            &format!("{} already exists. Overwrite it?", config_path.display()),
+            false,
+        )?
+    {
+        prompt.say("Nothing written.")?;
+        return Ok(());
+    }
+
+    if let Some(credentials) = credentials {
+        store.store(&provider_name, None, &credentials)?;
+        prompt.say(format!(
+            "Credentials stored for provider {} in {}",
+            provider_name,
+            store.location()
+        ))?;
+    }
+
+    write_config(config_path, &rendered)?;
+
+    prompt.say(format!("Configuration written to {}", config_path.display()))?;
+    prompt.say("Run 'ipdnsd check' to test it and 'ipdnsd install' to run it as a service.")?;
+
+    Ok(())
+}
+
+/// Write a configuration file, creating its directory if needed
+pub fn write_config(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    fs::write(path, contents)
+        .with_context(|| format!("Failed to write config file: {}", path.display()))
+}
+
+/// Get working credentials for a provider, returning new ones that need storing
+async fn connect<R: BufRead, W: Write>(
+    prompt: &mut Prompt<R, W>,
+    provider_name: &str,
+    store: &dyn CredentialStore,
+) -> Result<(Arc<dyn DnsProvider>, Option<Credentials>, Vec<String>)> {
+    if let Ok(Some(stored)) = store.get(provider_name, None) {
+        if prompt.confirm("Use the stored credentials?", true)? {
+            let provider = create_provider(provider_name, stored)?;
+            match list_domains(provider.as_ref()).await {
+                Ok(domains) => return Ok((provider, None, domains)),
+                Err(e) if ProviderError::is_auth(&e) => {
+                    prompt.say(format!(
+                        "{} rejected the stored credentials, enter new ones.",
+                        provider_name
+                    ))?;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    for _ in 0..CREDENTIAL_ATTEMPTS {
+        let api_key = prompt.ask("API Key", None)?;
+        let api_secret = rpassword::prompt_password("API Secret: ")?;
+        let credentials = Credentials {
+            api_key,
+            api_secret,
+        };
+
+        let provider = create_provider(provider_name, credentials.clone())?;
+        match list_domains(provider.as_ref()).await {
+            Ok(domains) => {
+                prompt.say(format!("Credentials accepted, found {} domains.", domains.len()))?;
+                return Ok((provider, Some(credentials), domains));
+            }
+            Err(e) if ProviderError::is_auth(&e) => {
+                prompt.say(format!("{} rejected the credentials, try again.", provider_name))?;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(anyhow!("{} rejected the credentials", provider_name))
+}
+
+async fn list_domains(provider: &dyn DnsProvider) -> Result<Vec<String>> {
+    let domains = provider.list_domains().await?;
+    if domains.is_empty() {
+        return Err(anyhow!(
+            "No active domains found in the {} account",
+            provider.provider_name()
+        ));
+    }
+    Ok(domains)
+}
+
+/// Addresses found on startup
+struct Detected {
+    external: Option<IpAddr>,
+    internal: Option<IpAddr>,
+}
+
+/// Show a domain's address records and ask which ones to keep updated
+async fn pick_records<R: BufRead, W: Write>(
+    prompt: &mut Prompt<R, W>,
+    provider: &dyn DnsProvider,
+    domain: &str,
+    detected: &Detected,
+) -> Result<Vec<InitEntry>> {
+    // name -> [(type, value)]
+    let mut existing: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+    for record in provider.list_records(domain).await? {
+        if record.record_type == "A" || record.record_type == "AAAA" {
+            existing
+                .entry(record.name)
+                .or_default()
+                .push((record.record_type, record.data));
+        }
+    }
+
+    if existing.is_empty() {
+        prompt.say(format!("{} has no A or AAAA records yet.", domain))?;
+    } else {
+        prompt.say(format!("Address records in {}:", domain))?;
+        for (name, records) in &existing {
+            for (record_type, data) in records {
+                prompt.say(format!("  {:<20} {:<5} {}", name, record_type, data))?;
+            }
+        }
+    }
+
+    let names = prompt.ask(
+        "Record names to update, comma separated (@ is the domain itself)",
+        Some("@"),
+    )?;
+
+    let mut entries = Vec::new();
+    for name in names.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let (ip_source, address) = if detected.internal.is_some()
+            && !prompt.confirm(&format!("Publish the public IP for {}?", name), true)?
+        {
+            (IpSource::Internal(Default::default()), detected.internal)
+        } else {
+            (IpSource::External, detected.external)
+        };
+
+        let records = existing.get(name).cloned().unwrap_or_default();
+        let default_types = default_record_types(&records, address);
+        let record_types = loop {
+            let answer = prompt.ask(
+                &format!("Record types for {} (A, AAAA or both)", name),
+                Some(&default_types.join(",")),
+            )?;
+            match parse_record_types(&answer) {
+                Some(types) => break types,
+                None => prompt.say("Enter A, AAAA or A,AAAA.")?,
+            }
+        };
+
+        for record_type in record_types {
+            let current = records.iter().find(|(existing, _)| existing == record_type);
+            let note = match current {
+                Some((_, data)) => format!("Currently {}", data),
+                None => "The record doesn't exist yet and is created on the first update".to_string(),
+            };
+            entries.push(InitEntry {
+                provider: provider.provider_name().to_string(),
+                credentials: None,
+                domain: domain.to_string(),
+                record_name: name.to_string(),
+                record_type: record_type.to_string(),
+                ip_source: ip_source.clone(),
+                ttl: None,
+                policy: AddressPolicy::default(),
+                note: Some(note),
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Record types suggested for a name: the ones it already has, otherwise the
+/// family of the address it will publish
+fn default_record_types(records: &[(String, String)], address: Option<IpAddr>) -> Vec<&'static str> {
+    let types: Vec<&'static str> = ["A", "AAAA"]
+        .into_iter()
+        .filter(|record_type| records.iter().any(|(existing, _)| existing == record_type))
+        .collect();
+    if !types.is_empty() {
+        return types;
+    }
+
+    match address {
+        Some(IpAddr::V6(_)) => vec!["AAAA"],
+        _ => vec!["A"],
+    }
+}
+
+/// Parse a comma separated list of A and AAAA
+fn parse_record_types(answer: &str) -> Option<Vec<&'static str>> {
+    let mut types = Vec::new();
+    for record_type in answer.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        let record_type = match record_type.to_uppercase().as_str() {
+            "A" => "A",
+            "AAAA" => "AAAA",
+            _ => return None,
+        };
+        if !types.contains(&record_type) {
+            types.push(record_type);
+        }
+    }
+    (!types.is_empty()).then_some(types)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_record_types() {
+        let records = |types: &[&str]| -> Vec<(String, String)> {
+            types
+                .iter()
+                .map(|t| (t.to_string(), "192.0.2.1".to_string()))
+                .collect()
+        };
+        let v4 = Some("198.51.100.1".parse().unwrap());
+        let v6 = Some("2001:db8::1".parse().unwrap());
+
+        assert_eq!(default_record_types(&records(&["AAAA"]), v4), ["AAAA"]);
+        assert_eq!(default_record_types(&records(&["AAAA", "A"]), v6), ["A", "AAAA"]);
+        assert_eq!(default_record_types(&[], v6), ["AAAA"]);
+        assert_eq!(default_record_types(&[], v4), ["A"]);
+        assert_eq!(default_record_types(&[], None), ["A"]);
+    }
+
+    #[test]
+    fn test_parse_record_types() {
+        assert_eq!(parse_record_types("aaaa"), Some(vec!["AAAA"]));
+        assert_eq!(parse_record_types("A, AAAA, a"), Some(vec!["A", "AAAA"]));
+        assert_eq!(parse_record_types("MX"), None);
+        assert_eq!(parse_record_types(" , "), None);
+    }
+}
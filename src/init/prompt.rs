@@ -0,0 +1,102 @@
+use std::io::{BufRead, Write};
+
+use anyhow::{anyhow, Result};
+
+/// Line based questions on a terminal
+pub struct Prompt<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Prompt<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self { input, output }
+    }
+
+    /// Ask for a line of text, falling back to `default` on an empty answer
+    pub fn ask(&mut self, question: &str, default: Option<&str>) -> Result<String> {
+        loop {
+            match default {
+                Some(default) => write!(self.output, "{} [{}]: ", question, default)?,
+                None => write!(self.output, "{}: ", question)?,
+            }
+            self.output.flush()?;
+
+            let answer = self.read_line()?;
+            if !answer.is_empty() {
+                return Ok(answer);
+            }
+            if let Some(default) = default {
+                return Ok(default.to_string());
+            }
+        }
+    }
+
+    /// Ask a yes/no question
+    pub fn confirm(&mut self, question: &str, default: bool) -> Result<bool> {
+        let hint = if default { "Y/n" } else { "y/N" };
+        loop {
+            write!(self.output, "{} [{}]: ", question, hint)?;
+            self.output.flush()?;
+
+            match self.read_line()?.to_lowercase().as_str() {
+                "" => return Ok(default),
+                "y" | "yes" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                _ => writeln!(self.output, "Please answer y or n.")?,
+            }
+        }
+    }
+
+    /// Pick one of `options` by number
+    pub fn choose(&mut self, question: &str, options: &[String], default: usize) -> Result<usize> {
+        writeln!(self.output, "{}", question)?;
+        for (i, option) in options.iter().enumerate() {
+            writeln!(self.output, "  {}) {}", i + 1, option)?;
+        }
+
+        loop {
+            let answer = self.ask("Choice", Some(&(default + 1).to_string()))?;
+            match answer.parse::<usize>() {
+                Ok(n) if (1..=options.len()).contains(&n) => return Ok(n - 1),
+                _ => writeln!(self.output, "Enter a number from 1 to {}.", options.len())?,
+            }
+        }
+    }
+
+    /// Print a line of output
+    pub fn say(&mut self, message: impl AsRef<str>) -> Result<()> {
+        writeln!(self.output, "{}", message.as_ref())?;
+        Ok(())
+    }
+
+    fn read_line(&mut self) -> Result<String> {
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Err(anyhow!("Input closed"));
+        }
+        Ok(line.trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_answers() {
+        let input = "\nmaybe\ny\n7\n2\n";
+        let mut output = Vec::new();
+        let mut prompt = Prompt::new(input.as_bytes(), &mut output);
+
+        assert_eq!(prompt.ask("Interval", Some("300")).unwrap(), "300");
+        assert!(prompt.confirm("Continue?", false).unwrap());
+        let options = vec!["godaddy".to_string(), "other".to_string()];
+        assert_eq!(prompt.choose("Provider", &options, 0).unwrap(), 1);
+        assert!(prompt.ask("Name", None).is_err());
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Please answer y or n."));
+        assert!(output.contains("Enter a number from 1 to 2."));
+    }
+}
@@ -0,0 +1,163 @@
+use std::fmt::Write;
+
+use crate::config::{AddressPolicy, AddressScope, IpSource};
+
+/// A generated configuration, from the `init` wizard or an import
+#[derive(Debug, Clone)]
+pub struct InitConfig {
+    /// Lines for the comment at the top of the file
+    pub comment: Vec<String>,
+    pub interval_seconds: u64,
+    pub entries: Vec<InitEntry>,
+}
+
+/// A record to keep updated
+#[derive(Debug, Clone)]
+pub struct InitEntry {
+    pub provider: String,
+    /// Credential profile, if not the provider's default credentials
+    pub credentials: Option<String>,
+    pub domain: String,
+    pub record_name: String,
+    pub record_type: String,
+    pub ip_source: IpSource,
+    pub ttl: Option<u32>,
+    pub policy: AddressPolicy,
+    /// Comment placed above the entry, e.g. the record's current value
+    pub note: Option<String>,
+}
+
+/// Render a commented configuration file
+pub fn render_config(config: &InitConfig) -> String {
+    let mut out = String::from("# ipdnsd Configuration File\n#\n");
+    for line in &config.comment {
+        match line.as_str() {
+            "" => out.push_str("#\n"),
+            line => {
+                let _ = writeln!(out, "# {}", line);
+            }
+        }
+    }
+    if !config.comment.is_empty() {
+        out.push_str("#\n");
+    }
+
+    out.push_str(
+        "# Check changes with `ipdnsd config validate`. config/ipdnsd.example.toml\n\
+         # in the source tree documents every option.\n\
+         \n\
+         [daemon]\n\
+         # How often to check for IP changes (in seconds)\n",
+    );
+    let _ = writeln!(out, "interval_seconds = {}", config.interval_seconds);
+    out.push_str(
+        "\n\
+         # Logging level: trace, debug, info, warn, error\n\
+         log_level = \"info\"\n",
+    );
+
+    for entry in &config.entries {
+        let fqdn = match entry.record_name.as_str() {
+            "@" => entry.domain.clone(),
+            name => format!("{}.{}", name, entry.domain),
+        };
+        let source = match &entry.ip_source {
+            IpSource::External => "your public IP".to_string(),
+            IpSource::Internal(selector) if entry.policy.scope == Some(AddressScope::Public) => {
+                match &selector.interface {
+                    Some(interface) => format!("the public IP of {}", interface),
+                    None => "your public IP".to_string(),
+                }
+            }
+            IpSource::Internal(_) => "your LAN IP".to_string(),
+            other => format!("the {} source", other),
+        };
+
+        let _ = writeln!(
+            out,
+            "\n# {} ({}) follows {}",
+            fqdn, entry.record_type, source
+        );
+        if let Some(note) = &entry.note {
+            let _ = writeln!(out, "# {}", note);
+        }
+        out.push_str("[[dns_entries]]\n");
+        let _ = writeln!(out, "provider = {}", quote(&entry.provider));
+        if let Some(credentials) = &entry.credentials {
+            let _ = writeln!(out, "credentials = {}", quote(credentials));
+        }
+        let _ = writeln!(out, "domain = {}", quote(&entry.domain));
+        let _ = writeln!(out, "record_name = {}", quote(&entry.record_name));
+        let _ = writeln!(out, "record_type = {}", quote(&entry.record_type));
+        let ip_source = toml::Value::try_from(&entry.ip_source)
+            .map(|value| value.to_string())
+            .unwrap_or_else(|_| quote(&entry.ip_source.to_string()));
+        let _ = writeln!(out, "ip_source = {}", ip_source);
+        match entry.ttl {
+            Some(ttl) => {
+                let _ = writeln!(out, "ttl = {}", ttl);
+            }
+            None => out.push_str("# ttl = 600                # Optional: TTL in seconds\n"),
+        }
+        if !entry.policy.is_default() {
+            if let Ok(policy) = toml::Value::try_from(&entry.policy) {
+                let _ = writeln!(out, "policy = {}", policy);
+            }
+        }
+    }
+
+    out
+}
+
+fn quote(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{validate, InternalSelector};
+
+    #[test]
+    fn test_render_config() {
+        let entry = InitEntry {
+            provider: "godaddy".to_string(),
+            credentials: None,
+            domain: "example.com".to_string(),
+            record_name: "@".to_string(),
+            record_type: "A".to_string(),
+            ip_source: IpSource::External,
+            ttl: None,
+            policy: AddressPolicy::default(),
+            note: Some("Currently 203.0.113.42".to_string()),
+        };
+        let config = InitConfig {
+            comment: vec!["Written by `ipdnsd init`.".to_string(), String::new(), "Edit freely.".to_string()],
+            interval_seconds: 120,
+            entries: vec![
+                entry.clone(),
+                InitEntry {
+                    record_name: "nas".to_string(),
+                    ip_source: IpSource::Internal(InternalSelector {
+                        interface: Some("eth0".to_string()),
+                        ..Default::default()
+                    }),
+                    ttl: Some(3600),
+                    note: None,
+                    ..entry
+                },
+            ],
+        };
+
+        let rendered = render_config(&config);
+        assert!(rendered.starts_with("# ipdnsd Configuration File\n#\n# Written by `ipdnsd init`.\n#\n# Edit freely.\n#\n"));
+        assert!(rendered.contains("# example.com (A) follows your public IP\n# Currently 203.0.113.42\n"));
+        assert!(rendered.contains("# nas.example.com (A) follows your LAN IP\n[[dns_entries]]\n"));
+        assert!(rendered.contains("ip_source = { internal = { interface = \"eth0\" } }\nttl = 3600\n"));
+
+        let settings = validate(&rendered, None).unwrap();
+        assert_eq!(settings.daemon.interval_seconds, 120);
+        assert_eq!(settings.dns_entries.len(), 2);
+        assert_eq!(settings.dns_entries[1].ip_source, config.entries[1].ip_source);
+    }
+}
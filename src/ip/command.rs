@@ -0,0 +1,88 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+
+use crate::config::CommandSpec;
+
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub async fn get_command_ip(source: &CommandSpec) -> Result<IpAddr> {
+    let stdout = source
+        .run_with_timeout("Command", COMMAND_TIMEOUT, Vec::new())
+        .await?;
+
+    parse_ip_output(&stdout)
+        .ok_or_else(|| anyhow!("No IP address found in output of {}", source.args[0]))
+}
+
+/// Return the first whitespace-separated token that is an IP address. A
+/// `/prefix` suffix (`2001:db8::1/64`) and a `label:` prefix (`addr:10.0.0.2`)
+/// are ignored.
+pub(super) fn parse_ip_output(stdout: &str) -> Option<IpAddr> {
+    let parse = |text: &str| {
+        text.trim_matches(|c: char| !c.is_ascii_hexdigit() && c != ':' && c != '.')
+            .parse()
+            .ok()
+    };
+
+    stdout.split_whitespace().find_map(|token| {
+        let address = token.split('/').next().unwrap_or(token);
+        parse(address).or_else(|| {
+            let (_, rest) = address.split_once(':')?;
+            parse(rest)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(args: &[&str], timeout_seconds: Option<u64>) -> CommandSpec {
+        CommandSpec {
+            args: args.iter().map(|s| s.to_string()).collect(),
+            timeout_seconds,
+        }
+    }
+
+    #[test]
+    fn test_parse_ip_output() {
+        assert_eq!(
+            parse_ip_output("203.0.113.9\n"),
+            Some("203.0.113.9".parse().unwrap())
+        );
+        assert_eq!(
+            parse_ip_output("WAN status: up\nAddress: 2001:db8::7,\n"),
+            Some("2001:db8::7".parse().unwrap())
+        );
+        assert_eq!(
+            parse_ip_output("inet addr:192.168.1.2  Bcast:192.168.1.255\n"),
+            Some("192.168.1.2".parse().unwrap())
+        );
+        assert_eq!(
+            parse_ip_output("inet6 2001:db8::1/64 scope global dynamic\n"),
+            Some("2001:db8::1".parse().unwrap())
+        );
+        assert_eq!(parse_ip_output("no address yet"), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_get_command_ip() {
+        let ip = get_command_ip(&command(&["echo", "ip=198.51.100.4"], None))
+            .await
+            .unwrap();
+        assert_eq!(ip, "198.51.100.4".parse::<IpAddr>().unwrap());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_get_command_ip_failures() {
+        assert!(get_command_ip(&command(&["false"], None)).await.is_err());
+        assert!(get_command_ip(&command(&["sleep", "5"], Some(1)))
+            .await
+            .is_err());
+        assert!(get_command_ip(&command(&[], None)).await.is_err());
+    }
+}
@@ -1,31 +1,57 @@
-use std::net::IpAddr;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::time::Duration;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
 use reqwest::Client;
+use tokio::task::JoinSet;
 
-const EXTERNAL_IP_SERVICES: &[&str] = &[
-    "https://api.ipify.org",
-    "https://ifconfig.me/ip",
-    "https://icanhazip.com",
-    "https://ipinfo.io/ip",
-    "https://checkip.amazonaws.com",
-];
+use crate::config::{
+    AddressFamily, ConsensusConfig, ExternalIpConfig, ExternalIpService, ResponseFormat,
+};
 
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_JSON_POINTER: &str = "/ip";
 
-pub async fn get_external_ip() -> Result<IpAddr> {
-    let client = Client::builder()
-        .timeout(REQUEST_TIMEOUT)
-        .build()?;
+/// Look up the external address of `family`. Services limited to another
+/// family are skipped and the others are asked over `family`, so a dual-stack
+/// host gets comparable answers from every service.
+pub async fn get_external_ip(config: &ExternalIpConfig, family: AddressFamily) -> Result<IpAddr> {
+    let services = services_for(&config.services, family);
+    match &config.consensus {
+        Some(consensus) => consensus_ip(&services, consensus).await,
+        None => first_ip(&services).await,
+    }
+}
+
+fn services_for(services: &[ExternalIpService], family: AddressFamily) -> Vec<ExternalIpService> {
+    services
+        .iter()
+        .filter(|service| {
+            family == AddressFamily::Any
+                || service.family == AddressFamily::Any
+                || service.family == family
+        })
+        .map(|service| {
+            let mut service = service.clone();
+            if family != AddressFamily::Any {
+                service.family = family;
+            }
+            service
+        })
+        .collect()
+}
 
+/// Return the answer of the first service that responds
+async fn first_ip(services: &[ExternalIpService]) -> Result<IpAddr> {
     let mut last_error = None;
 
-    for service in EXTERNAL_IP_SERVICES {
-        match fetch_ip(&client, service).await {
+    for service in services {
+        match fetch_ip(service).await {
             Ok(ip) => return Ok(ip),
             Err(e) => {
-                tracing::debug!("Failed to get IP from {}: {}", service, e);
+                tracing::debug!("Failed to get IP from {}: {}", service.url, e);
                 last_error = Some(e);
             }
         }
@@ -34,17 +60,147 @@ pub async fn get_external_ip() -> Result<IpAddr> {
     Err(last_error.unwrap_or_else(|| anyhow!("No IP services available")))
 }
 
-async fn fetch_ip(client: &Client, url: &str) -> Result<IpAddr> {
+/// Query several services in parallel and accept an IP only if enough of them agree
+async fn consensus_ip(
+    services: &[ExternalIpService],
+    consensus: &ConsensusConfig,
+) -> Result<IpAddr> {
+    if consensus.quorum < consensus.min_quorum() || consensus.quorum > consensus.services {
+        return Err(anyhow!(
+            "Invalid consensus settings: quorum {} with {} services",
+            consensus.quorum,
+            consensus.services
+        ));
+    }
+
+    let mut tasks = JoinSet::new();
+    for service in services.iter().take(consensus.services) {
+        let service = service.clone();
+        tasks.spawn(async move {
+            let result = fetch_ip(&service).await;
+            (service.url, result)
+        });
+    }
+
+    let mut answers: Vec<(String, IpAddr)> = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        let (url, result) = joined?;
+        match result {
+            Ok(ip) => answers.push((url, ip)),
+            Err(e) => tracing::debug!("Failed to get IP from {}: {}", url, e),
+        }
+    }
+
+    let mut votes: HashMap<IpAddr, usize> = HashMap::new();
+    for (_, ip) in &answers {
+        *votes.entry(*ip).or_default() += 1;
+    }
+
+    let mut ranked: Vec<(IpAddr, usize)> = votes.into_iter().collect();
+    ranked.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    let (ip, count) = *ranked
+        .first()
+        .ok_or_else(|| anyhow!("No IP service answered"))?;
+
+    // Two addresses with the same support can't be told apart
+    if let Some((other, _)) = ranked.get(1).filter(|(_, other_count)| *other_count == count) {
+        return Err(anyhow!(
+            "No consensus on external IP: {} and {} were each reported by {} services",
+            ip,
+            other,
+            count
+        ));
+    }
+
+    if count < consensus.quorum {
+        return Err(anyhow!(
+            "No consensus on external IP: best answer {} from {} of {} services, {} required",
+            ip,
+            count,
+            answers.len(),
+            consensus.quorum
+        ));
+    }
+
+    for (url, answer) in &answers {
+        if *answer != ip {
+            tracing::warn!(
+                "External IP service {} reported {} but consensus is {}",
+                url,
+                answer,
+                ip
+            );
+        }
+    }
+
+    Ok(ip)
+}
+
+async fn fetch_ip(service: &ExternalIpService) -> Result<IpAddr> {
+    let timeout = service
+        .timeout_seconds
+        .map(Duration::from_secs)
+        .unwrap_or(REQUEST_TIMEOUT);
+
+    let mut builder = Client::builder().timeout(timeout);
+    // Binding to the unspecified address of a family forces the connection over it
+    builder = match service.family {
+        AddressFamily::Any => builder,
+        AddressFamily::Ipv4 => builder.local_address(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+        AddressFamily::Ipv6 => builder.local_address(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+    };
+    let client = builder.build()?;
+
     let response = client
-        .get(url)
+        .get(&service.url)
         .send()
         .await?
         .error_for_status()?
         .text()
         .await?;
 
-    let ip_str = response.trim();
-    let ip: IpAddr = ip_str.parse()?;
+    parse_response(service, &response)
+}
+
+fn parse_response(service: &ExternalIpService, body: &str) -> Result<IpAddr> {
+    let text = match service.format {
+        ResponseFormat::Text => body.to_string(),
+        ResponseFormat::Json => {
+            let value: serde_json::Value =
+                serde_json::from_str(body).context("Invalid JSON response")?;
+            let pointer = service.json_pointer.as_deref().unwrap_or(DEFAULT_JSON_POINTER);
+            match value.pointer(pointer) {
+                Some(serde_json::Value::String(s)) => s.clone(),
+                Some(other) => other.to_string(),
+                None => return Err(anyhow!("JSON pointer {} not found in response", pointer)),
+            }
+        }
+    };
+
+    let ip_str = match &service.regex {
+        Some(pattern) => {
+            let re = Regex::new(pattern).with_context(|| format!("Invalid regex: {}", pattern))?;
+            let captures = re
+                .captures(&text)
+                .ok_or_else(|| anyhow!("Regex {} did not match response", pattern))?;
+            captures
+                .get(1)
+                .or_else(|| captures.get(0))
+                .map(|m| m.as_str().to_string())
+                .unwrap_or_default()
+        }
+        None => text,
+    };
+
+    let ip: IpAddr = ip_str.trim().parse()?;
+
+    if !service.family.matches(&ip) {
+        return Err(anyhow!(
+            "Service returned {} but an {} address was expected",
+            ip,
+            service.family
+        ));
+    }
 
     Ok(ip)
 }
@@ -52,6 +208,24 @@ async fn fetch_ip(client: &Client, url: &str) -> Result<IpAddr> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn echo_server(body: &str) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn services(servers: &[&MockServer]) -> Vec<ExternalIpService> {
+        servers
+            .iter()
+            .map(|server| ExternalIpService::new(&server.uri()))
+            .collect()
+    }
 
     #[tokio::test]
     async fn test_fetch_ip_format() {
@@ -68,4 +242,125 @@ mod tests {
             assert!(ip.is_ok(), "Failed to parse: {}", case);
         }
     }
+
+    #[tokio::test]
+    async fn test_consensus_ignores_dissenting_service() {
+        let a = echo_server("203.0.113.5\n").await;
+        let b = echo_server("198.51.100.7\n").await;
+        let c = echo_server("203.0.113.5\n").await;
+        let services = services(&[&a, &b, &c]);
+
+        let ip = consensus_ip(&services, &ConsensusConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(ip, "203.0.113.5".parse::<IpAddr>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_consensus_requires_quorum() {
+        let a = echo_server("203.0.113.5").await;
+        let b = echo_server("198.51.100.7").await;
+        let c = MockServer::start().await; // answers 404
+        let services = services(&[&a, &b, &c]);
+
+        let result = consensus_ip(&services, &ConsensusConfig::default()).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_consensus_rejects_tie() {
+        let a = echo_server("203.0.113.5").await;
+        let b = echo_server("203.0.113.5").await;
+        let c = echo_server("198.51.100.7").await;
+        let d = echo_server("198.51.100.7").await;
+        let services = services(&[&a, &b, &c, &d]);
+
+        let tie = consensus_ip(&services, &ConsensusConfig { services: 4, quorum: 3 })
+            .await
+            .unwrap_err();
+        assert!(tie.to_string().contains("each reported by 2 services"));
+
+        // A quorum two addresses could both reach is refused outright
+        let minority = ConsensusConfig { services: 4, quorum: 2 };
+        assert!(consensus_ip(&services, &minority).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mixed_families_only_count_requested_family() {
+        // Dual-stack services may answer over either family unless told which
+        let a = echo_server("2001:db8::5").await;
+        let b = echo_server("203.0.113.5").await;
+        let c = echo_server("2001:db8::5").await;
+        let d = echo_server("203.0.113.5").await;
+        let e = echo_server("203.0.113.5").await;
+
+        let config = ExternalIpConfig {
+            services: services(&[&a, &b, &c, &d, &e]),
+            consensus: None,
+        };
+        let ip = get_external_ip(&config, AddressFamily::Ipv4).await.unwrap();
+        assert_eq!(ip, "203.0.113.5".parse::<IpAddr>().unwrap());
+
+        let config = ExternalIpConfig {
+            consensus: Some(ConsensusConfig { services: 5, quorum: 3 }),
+            ..config
+        };
+        let ip = get_external_ip(&config, AddressFamily::Ipv4).await.unwrap();
+        assert_eq!(ip, "203.0.113.5".parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn test_services_for_family() {
+        let mut v4_only = ExternalIpService::new("http://v4");
+        v4_only.family = AddressFamily::Ipv4;
+        let services = vec![v4_only, ExternalIpService::new("http://any")];
+
+        let v6 = services_for(&services, AddressFamily::Ipv6);
+        assert_eq!(v6.len(), 1);
+        assert_eq!(v6[0].url, "http://any");
+        assert_eq!(v6[0].family, AddressFamily::Ipv6);
+
+        assert_eq!(services_for(&services, AddressFamily::Ipv4).len(), 2);
+        assert_eq!(services_for(&services, AddressFamily::Any)[1].family, AddressFamily::Any);
+    }
+
+    #[tokio::test]
+    async fn test_first_ip_falls_through_to_json_service() {
+        let broken = MockServer::start().await; // answers 404
+        let json = echo_server(r#"{"ip": "203.0.113.9", "city": "Berlin"}"#).await;
+
+        let mut json_service = ExternalIpService::new(&json.uri());
+        json_service.format = ResponseFormat::Json;
+
+        let config = ExternalIpConfig {
+            services: vec![ExternalIpService::new(&broken.uri()), json_service],
+            consensus: None,
+        };
+
+        let ip = get_external_ip(&config, AddressFamily::Any).await.unwrap();
+        assert_eq!(ip, "203.0.113.9".parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn test_parse_response_with_pointer_and_regex() {
+        let mut service = ExternalIpService::new("http://example");
+        service.format = ResponseFormat::Json;
+        service.json_pointer = Some("/data/address".to_string());
+        let ip = parse_response(&service, r#"{"data": {"address": "198.51.100.3"}}"#).unwrap();
+        assert_eq!(ip, "198.51.100.3".parse::<IpAddr>().unwrap());
+
+        let mut service = ExternalIpService::new("http://example");
+        service.regex = Some(r"Current IP Address: ([0-9.]+)".to_string());
+        let body = "<html><body>Current IP Address: 203.0.113.77</body></html>";
+        let ip = parse_response(&service, body).unwrap();
+        assert_eq!(ip, "203.0.113.77".parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn test_parse_response_checks_family() {
+        let mut service = ExternalIpService::new("http://example");
+        service.family = AddressFamily::Ipv6;
+        assert!(parse_response(&service, "203.0.113.77").is_err());
+        assert!(parse_response(&service, "2001:db8::1").is_ok());
+    }
 }
@@ -0,0 +1,96 @@
+use std::net::IpAddr;
+
+use anyhow::{anyhow, Result};
+use tracing::{debug, warn};
+
+use super::{check_health, get_ip};
+use crate::config::{AddressFamily, ExternalIpConfig, FailoverTarget, IpSource};
+
+/// Return the address of the first target that resolves and passes its health
+/// check, with the source that produced it
+pub async fn get_failover_ip<'a>(
+    targets: &'a [FailoverTarget],
+    external: &ExternalIpConfig,
+    family: AddressFamily,
+) -> Result<(IpAddr, &'a IpSource)> {
+    for (index, target) in targets.iter().enumerate() {
+        let (ip, source) = match Box::pin(get_ip(&target.ip_source, external, family)).await {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                warn!("Failover target {} ({}) unavailable: {}", index + 1, target.ip_source, e);
+                continue;
+            }
+        };
+
+        match &target.health_check {
+            Some(check) => match check_health(check, ip).await {
+                Ok(()) => {
+                    debug!("Failover target {} ({}) is healthy", index + 1, ip);
+                    return Ok((ip, source));
+                }
+                Err(e) => warn!("Failover target {} ({}) is unhealthy: {}", index + 1, ip, e),
+            },
+            None => return Ok((ip, source)),
+        }
+    }
+
+    Err(anyhow!("None of the {} failover targets is healthy", targets.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::HealthCheck;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn health_server(status: u16) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(status))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn target(ip: &str, server: &MockServer) -> FailoverTarget {
+        FailoverTarget {
+            ip_source: IpSource::Static(ip.parse().unwrap()),
+            health_check: Some(HealthCheck::Http {
+                url: server.uri(),
+                expect_status: None,
+                timeout_seconds: Some(2),
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_first_healthy_target_wins() {
+        let down = health_server(503).await;
+        let up = health_server(200).await;
+
+        let targets = vec![
+            target("198.51.100.10", &down),
+            target("198.51.100.20", &up),
+            target("198.51.100.30", &up),
+        ];
+
+        let external = ExternalIpConfig::default();
+        let (ip, source) = get_failover_ip(&targets, &external, AddressFamily::Ipv4)
+            .await
+            .unwrap();
+        assert_eq!(ip, "198.51.100.20".parse::<IpAddr>().unwrap());
+        assert_eq!(source, &targets[1].ip_source);
+    }
+
+    #[tokio::test]
+    async fn test_no_healthy_target() {
+        let down = health_server(500).await;
+        let targets = vec![target("198.51.100.10", &down)];
+
+        let external = ExternalIpConfig::default();
+        assert!(get_failover_ip(&targets, &external, AddressFamily::Ipv4)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,35 @@
+use std::net::IpAddr;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+
+use super::command::parse_ip_output;
+
+pub async fn get_file_ip(path: &Path) -> Result<IpAddr> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read IP file: {}", path.display()))?;
+
+    parse_ip_output(&content)
+        .ok_or_else(|| anyhow!("No IP address found in {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_file_ip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wan-ip");
+
+        assert!(get_file_ip(&path).await.is_err());
+
+        std::fs::write(&path, "198.51.100.23\n").unwrap();
+        let ip = get_file_ip(&path).await.unwrap();
+        assert_eq!(ip, "198.51.100.23".parse::<IpAddr>().unwrap());
+
+        std::fs::write(&path, "").unwrap();
+        assert!(get_file_ip(&path).await.is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use reqwest::Client;
+use tokio::net::TcpStream;
+
+use crate::config::HealthCheck;
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Run a health check against a candidate address
+pub async fn check_health(check: &HealthCheck, ip: IpAddr) -> Result<()> {
+    match check {
+        HealthCheck::Tcp {
+            port,
+            timeout_seconds,
+        } => {
+            let addr = SocketAddr::new(ip, *port);
+            tokio::time::timeout(timeout(*timeout_seconds), TcpStream::connect(addr))
+                .await
+                .map_err(|_| anyhow!("TCP connect to {} timed out", addr))?
+                .with_context(|| format!("TCP connect to {} failed", addr))?;
+            Ok(())
+        }
+        HealthCheck::Http {
+            url,
+            expect_status,
+            timeout_seconds,
+        } => {
+            let host = match ip {
+                IpAddr::V4(v4) => v4.to_string(),
+                IpAddr::V6(v6) => format!("[{}]", v6),
+            };
+            let url = url.replace("{ip}", &host);
+
+            let client = Client::builder()
+                .timeout(timeout(*timeout_seconds))
+                .build()?;
+            let status = client
+                .get(&url)
+                .send()
+                .await
+                .with_context(|| format!("HTTP check of {} failed", url))?
+                .status();
+
+            let healthy = match expect_status {
+                Some(expected) => status.as_u16() == *expected,
+                None => status.is_success(),
+            };
+            if !healthy {
+                return Err(anyhow!("HTTP check of {} returned {}", url, status));
+            }
+            Ok(())
+        }
+    }
+}
+
+fn timeout(seconds: Option<u64>) -> Duration {
+    seconds.map(Duration::from_secs).unwrap_or(CHECK_TIMEOUT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const LOCALHOST: IpAddr = IpAddr::V4(std::net::Ipv4Addr::LOCALHOST);
+
+    #[tokio::test]
+    async fn test_tcp_check() {
+        let listener = tokio::net::TcpListener::bind((LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let check = HealthCheck::Tcp {
+            port,
+            timeout_seconds: Some(2),
+        };
+        assert!(check_health(&check, LOCALHOST).await.is_ok());
+
+        drop(listener);
+        assert!(check_health(&check, LOCALHOST).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_http_check_substitutes_ip() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/health"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+        let port = server.address().port();
+
+        let check = HealthCheck::Http {
+            url: format!("http://{{ip}}:{}/health", port),
+            expect_status: Some(204),
+            timeout_seconds: None,
+        };
+        assert!(check_health(&check, LOCALHOST).await.is_ok());
+
+        let check = HealthCheck::Http {
+            url: format!("http://{{ip}}:{}/missing", port),
+            expect_status: None,
+            timeout_seconds: None,
+        };
+        assert!(check_health(&check, LOCALHOST).await.is_err());
+    }
+}
@@ -1,9 +1,138 @@
-use std::net::IpAddr;
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv6Addr};
 
 use anyhow::{anyhow, Result};
 
-pub fn get_internal_ip() -> Result<IpAddr> {
-    local_ip_address::local_ip().map_err(|e| anyhow!("Failed to get local IP: {}", e))
+use crate::config::{AddressFamily, InternalSelector, Ipv6Scope};
+
+/// IFA_F_TEMPORARY from linux/if_addr.h
+#[cfg(target_os = "linux")]
+const IFA_F_TEMPORARY: u32 = 0x01;
+
+/// Get the local address picked by `selector`, in `family` (the one the
+/// record holds) unless the selector asks for a family itself
+pub fn get_internal_ip(selector: &InternalSelector, family: AddressFamily) -> Result<IpAddr> {
+    let selector = with_family(selector, family);
+
+    // Without other options, the address of the default route is used
+    let options = InternalSelector {
+        family: AddressFamily::Any,
+        ..selector.clone()
+    };
+    if options.is_default() {
+        let ip = match selector.family {
+            AddressFamily::Ipv6 => local_ip_address::local_ipv6(),
+            AddressFamily::Any | AddressFamily::Ipv4 => local_ip_address::local_ip(),
+        };
+        return ip.map_err(|e| anyhow!("Failed to get local IP: {}", e));
+    }
+
+    let interfaces = local_ip_address::list_afinet_netifas()
+        .map_err(|e| anyhow!("Failed to list network interfaces: {}", e))?;
+
+    select_address(&interfaces, &temporary_ipv6_addresses(), &selector)
+        .ok_or_else(|| anyhow!("No local address matches the internal IP selection"))
+}
+
+/// `selector`, limited to `family` if it doesn't choose a family itself
+fn with_family(selector: &InternalSelector, family: AddressFamily) -> InternalSelector {
+    InternalSelector {
+        family: match selector.family {
+            AddressFamily::Any => family,
+            own => own,
+        },
+        ..selector.clone()
+    }
+}
+
+/// Pick the best address from `(interface, address)` pairs according to the selector
+fn select_address(
+    interfaces: &[(String, IpAddr)],
+    temporary: &HashSet<Ipv6Addr>,
+    selector: &InternalSelector,
+) -> Option<IpAddr> {
+    interfaces
+        .iter()
+        .filter(|(name, ip)| {
+            !ip.is_loopback()
+                && selector.interface.as_ref().is_none_or(|wanted| wanted == name)
+                && !selector
+                    .exclude
+                    .iter()
+                    .any(|pattern| matches_pattern(pattern, name))
+                && selector.cidr.is_none_or(|net| net.contains(ip))
+                && selector.family.matches(ip)
+        })
+        .enumerate()
+        .min_by_key(|(index, (_, ip))| {
+            let (wrong_scope, wrong_temporary) = match ip {
+                IpAddr::V4(_) => (false, false),
+                IpAddr::V6(v6) => (
+                    ipv6_scope(v6) != selector.ipv6_scope,
+                    temporary.contains(v6) != selector.prefer_temporary,
+                ),
+            };
+            // IPv4 first, then the preferred IPv6 scope and kind, then interface order
+            (ip.is_ipv6(), wrong_scope, wrong_temporary, *index)
+        })
+        .map(|(_, (_, ip))| *ip)
+}
+
+fn ipv6_scope(ip: &Ipv6Addr) -> Ipv6Scope {
+    let first = ip.segments()[0];
+    if first & 0xffc0 == 0xfe80 {
+        Ipv6Scope::LinkLocal
+    } else if first & 0xfe00 == 0xfc00 {
+        Ipv6Scope::Ula
+    } else {
+        Ipv6Scope::Global
+    }
+}
+
+/// Match an interface name against a pattern where `*` matches any run of
+/// characters and `?` matches a single character
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    fn matches(pattern: &[char], name: &[char]) -> bool {
+        match pattern.split_first() {
+            None => name.is_empty(),
+            Some(('*', rest)) => (0..=name.len()).any(|i| matches(rest, &name[i..])),
+            Some(('?', rest)) => !name.is_empty() && matches(rest, &name[1..]),
+            Some((c, rest)) => name.first() == Some(c) && matches(rest, &name[1..]),
+        }
+    }
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    matches(&pattern, &name)
+}
+
+#[cfg(target_os = "linux")]
+fn temporary_ipv6_addresses() -> HashSet<Ipv6Addr> {
+    std::fs::read_to_string("/proc/net/if_inet6")
+        .map(|content| parse_if_inet6(&content))
+        .unwrap_or_default()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn temporary_ipv6_addresses() -> HashSet<Ipv6Addr> {
+    HashSet::new()
+}
+
+/// Parse /proc/net/if_inet6 and return the addresses flagged as temporary
+#[cfg(target_os = "linux")]
+fn parse_if_inet6(content: &str) -> HashSet<Ipv6Addr> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (address, flags) = (fields.first()?, fields.get(4)?);
+            let flags = u32::from_str_radix(flags, 16).ok()?;
+            if flags & IFA_F_TEMPORARY == 0 {
+                return None;
+            }
+            u128::from_str_radix(address, 16).ok().map(Ipv6Addr::from)
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -13,7 +142,7 @@ mod tests {
     #[test]
     fn test_get_internal_ip() {
         // This should work on most systems
-        let result = get_internal_ip();
+        let result = get_internal_ip(&InternalSelector::default(), AddressFamily::Any);
         // We don't assert success because CI environments may not have a network interface
         if let Ok(ip) = result {
             // Should be a private IP or localhost
@@ -38,4 +167,116 @@ mod tests {
             }
         }
     }
+
+    fn interfaces() -> Vec<(String, IpAddr)> {
+        [
+            ("lo", "127.0.0.1"),
+            ("docker0", "172.17.0.1"),
+            ("eth0", "192.168.10.20"),
+            ("eth0", "fe80::1"),
+            ("eth0", "2001:db8::20"),
+            ("eth0", "2001:db8::abcd"),
+            ("eth0", "fd00::20"),
+            ("tun0", "10.8.0.2"),
+        ]
+        .into_iter()
+        .map(|(name, ip)| (name.to_string(), ip.parse().unwrap()))
+        .collect()
+    }
+
+    fn select(selector: InternalSelector) -> Option<String> {
+        let temporary = HashSet::from(["2001:db8::abcd".parse().unwrap()]);
+        select_address(&interfaces(), &temporary, &selector).map(|ip| ip.to_string())
+    }
+
+    #[test]
+    fn test_select_by_interface_and_exclude() {
+        let selector = InternalSelector {
+            interface: Some("tun0".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(select(selector).as_deref(), Some("10.8.0.2"));
+
+        let selector = InternalSelector {
+            exclude: vec!["docker*".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(select(selector).as_deref(), Some("192.168.10.20"));
+    }
+
+    #[test]
+    fn test_select_by_cidr() {
+        let selector = InternalSelector {
+            cidr: Some("10.8.0.0/24".parse().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(select(selector).as_deref(), Some("10.8.0.2"));
+
+        let selector = InternalSelector {
+            cidr: Some("10.99.0.0/16".parse().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(select(selector), None);
+    }
+
+    #[test]
+    fn test_select_ipv6_preferences() {
+        let v6 = InternalSelector {
+            family: AddressFamily::Ipv6,
+            ..Default::default()
+        };
+        assert_eq!(select(v6.clone()).as_deref(), Some("2001:db8::20"));
+
+        let temporary = InternalSelector {
+            prefer_temporary: true,
+            ..v6.clone()
+        };
+        assert_eq!(select(temporary).as_deref(), Some("2001:db8::abcd"));
+
+        let ula = InternalSelector {
+            ipv6_scope: Ipv6Scope::Ula,
+            ..v6
+        };
+        assert_eq!(select(ula).as_deref(), Some("fd00::20"));
+    }
+
+    #[test]
+    fn test_record_family() {
+        // An AAAA entry with the default internal source
+        let aaaa = with_family(&InternalSelector::default(), AddressFamily::Ipv6);
+        assert_eq!(select(aaaa).as_deref(), Some("2001:db8::20"));
+
+        let a = with_family(&InternalSelector::default(), AddressFamily::Ipv4);
+        assert_eq!(select(a).as_deref(), Some("172.17.0.1"));
+
+        // The selector's own family wins
+        let own = InternalSelector {
+            family: AddressFamily::Ipv4,
+            ..Default::default()
+        };
+        assert_eq!(with_family(&own, AddressFamily::Ipv6).family, AddressFamily::Ipv4);
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("docker*", "docker0"));
+        assert!(matches_pattern("veth*", "veth1a2b3c"));
+        assert!(matches_pattern("tun?", "tun0"));
+        assert!(!matches_pattern("tun?", "tun10"));
+        assert!(!matches_pattern("docker*", "eth0"));
+        assert!(matches_pattern("*", ""));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_if_inet6() {
+        let content = "\
+20010db8000000000000000000000020 02 40 00 00     eth0
+20010db800000000000000000000abcd 02 40 00 01     eth0
+fe800000000000000000000000000001 02 40 20 80     eth0
+";
+        let temporary = parse_if_inet6(content);
+        assert_eq!(temporary.len(), 1);
+        assert!(temporary.contains(&"2001:db8::abcd".parse().unwrap()));
+    }
 }
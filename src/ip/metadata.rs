@@ -0,0 +1,198 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use reqwest::{Client, RequestBuilder};
+
+use crate::config::{CloudProvider, MetadataAddress, MetadataSource};
+
+const LINK_LOCAL_ENDPOINT: &str = "http://169.254.169.254";
+const GCP_ENDPOINT: &str = "http://metadata.google.internal";
+const AZURE_API_VERSION: &str = "2021-02-01";
+const AWS_TOKEN_TTL_SECONDS: &str = "300";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub async fn get_metadata_ip(source: &MetadataSource) -> Result<IpAddr> {
+    // Metadata services are only reachable directly, never through a proxy
+    let client = Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .no_proxy()
+        .build()?;
+
+    let base = source
+        .endpoint
+        .as_deref()
+        .unwrap_or(match source.provider {
+            CloudProvider::Gcp => GCP_ENDPOINT,
+            _ => LINK_LOCAL_ENDPOINT,
+        })
+        .trim_end_matches('/');
+
+    let request = match source.provider {
+        CloudProvider::Aws => {
+            let token = aws_token(&client, base).await?;
+            let item = match source.address {
+                MetadataAddress::Public => "public-ipv4",
+                MetadataAddress::Private => "local-ipv4",
+            };
+            client
+                .get(format!("{}/latest/meta-data/{}", base, item))
+                .header("X-aws-ec2-metadata-token", token)
+        }
+        CloudProvider::Gcp => {
+            let item = match source.address {
+                MetadataAddress::Public => "access-configs/0/external-ip",
+                MetadataAddress::Private => "ip",
+            };
+            client
+                .get(format!(
+                    "{}/computeMetadata/v1/instance/network-interfaces/0/{}",
+                    base, item
+                ))
+                .header("Metadata-Flavor", "Google")
+        }
+        CloudProvider::Azure => {
+            let item = match source.address {
+                MetadataAddress::Public => "publicIpAddress",
+                MetadataAddress::Private => "privateIpAddress",
+            };
+            client
+                .get(format!(
+                    "{}/metadata/instance/network/interface/0/ipv4/ipAddress/0/{}",
+                    base, item
+                ))
+                .query(&[("api-version", AZURE_API_VERSION), ("format", "text")])
+                .header("Metadata", "true")
+        }
+        CloudProvider::OpenStack => {
+            // OpenStack serves the EC2-compatible metadata API without tokens
+            let item = match source.address {
+                MetadataAddress::Public => "public-ipv4",
+                MetadataAddress::Private => "local-ipv4",
+            };
+            client.get(format!("{}/latest/meta-data/{}", base, item))
+        }
+    };
+
+    let body = send(request).await?;
+    let body = body.trim();
+    if body.is_empty() {
+        return Err(anyhow!("Instance metadata has no {:?} address", source.address));
+    }
+
+    body.parse()
+        .with_context(|| format!("Invalid address in instance metadata: {}", body))
+}
+
+/// Fetch an IMDSv2 session token
+async fn aws_token(client: &Client, base: &str) -> Result<String> {
+    let request = client
+        .put(format!("{}/latest/api/token", base))
+        .header("X-aws-ec2-metadata-token-ttl-seconds", AWS_TOKEN_TTL_SECONDS);
+
+    send(request).await.context("Failed to get IMDSv2 token")
+}
+
+async fn send(request: RequestBuilder) -> Result<String> {
+    let response = request
+        .send()
+        .await
+        .context("Failed to reach instance metadata service")?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Instance metadata error ({})", response.status()));
+    }
+
+    Ok(response.text().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn source(
+        provider: CloudProvider,
+        address: MetadataAddress,
+        server: &MockServer,
+    ) -> MetadataSource {
+        MetadataSource {
+            provider,
+            address,
+            endpoint: Some(server.uri()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_aws_imdsv2() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/latest/api/token"))
+            .and(header("X-aws-ec2-metadata-token-ttl-seconds", AWS_TOKEN_TTL_SECONDS))
+            .respond_with(ResponseTemplate::new(200).set_body_string("secret-token"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/latest/meta-data/public-ipv4"))
+            .and(header("X-aws-ec2-metadata-token", "secret-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("203.0.113.50"))
+            .mount(&server)
+            .await;
+
+        let ip = get_metadata_ip(&source(CloudProvider::Aws, MetadataAddress::Public, &server))
+            .await
+            .unwrap();
+        assert_eq!(ip, "203.0.113.50".parse::<IpAddr>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_gcp_requires_header() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/computeMetadata/v1/instance/network-interfaces/0/ip"))
+            .and(header("Metadata-Flavor", "Google"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("10.128.0.7"))
+            .mount(&server)
+            .await;
+
+        let ip = get_metadata_ip(&source(CloudProvider::Gcp, MetadataAddress::Private, &server))
+            .await
+            .unwrap();
+        assert_eq!(ip, "10.128.0.7".parse::<IpAddr>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_azure_imds() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/metadata/instance/network/interface/0/ipv4/ipAddress/0/publicIpAddress",
+            ))
+            .and(query_param("format", "text"))
+            .and(header("Metadata", "true"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("198.51.100.80"))
+            .mount(&server)
+            .await;
+
+        let ip = get_metadata_ip(&source(CloudProvider::Azure, MetadataAddress::Public, &server))
+            .await
+            .unwrap();
+        assert_eq!(ip, "198.51.100.80".parse::<IpAddr>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_openstack_without_public_address() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/latest/meta-data/public-ipv4"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(""))
+            .mount(&server)
+            .await;
+
+        let result =
+            get_metadata_ip(&source(CloudProvider::OpenStack, MetadataAddress::Public, &server))
+                .await;
+        assert!(result.is_err());
+    }
+}
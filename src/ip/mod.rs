@@ -1,17 +1,45 @@
+mod command;
 mod external;
+mod failover;
+mod file;
+mod health;
 mod internal;
+mod metadata;
+mod policy;
+mod prefix;
 
+pub use command::get_command_ip;
 pub use external::get_external_ip;
+pub use failover::get_failover_ip;
+pub use file::get_file_ip;
+pub use health::check_health;
 pub use internal::get_internal_ip;
+pub use metadata::get_metadata_ip;
+pub use policy::{check_address, default_scope};
+pub use prefix::get_prefix_ip;
 
 use std::net::IpAddr;
 
-use crate::config::IpSource;
+use crate::config::{AddressFamily, ExternalIpConfig, IpSource};
 use anyhow::Result;
 
-pub async fn get_ip(source: &IpSource) -> Result<IpAddr> {
-    match source {
-        IpSource::External => get_external_ip().await,
-        IpSource::Internal => get_internal_ip(),
-    }
+/// Get the address `source` yields, with the source that produced it: `source`
+/// itself, or the selected target of a failover. `family` is the one the record
+/// holds and is used to look up external addresses over it.
+pub async fn get_ip<'a>(
+    source: &'a IpSource,
+    external: &ExternalIpConfig,
+    family: AddressFamily,
+) -> Result<(IpAddr, &'a IpSource)> {
+    let ip = match source {
+        IpSource::External => get_external_ip(external, family).await,
+        IpSource::Internal(selector) => get_internal_ip(selector, family),
+        IpSource::Command(command) => get_command_ip(command).await,
+        IpSource::Prefix(prefix) => get_prefix_ip(prefix, external).await,
+        IpSource::Static(ip) => Ok(*ip),
+        IpSource::File(path) => get_file_ip(path).await,
+        IpSource::Metadata(metadata) => get_metadata_ip(metadata).await,
+        IpSource::Failover(targets) => return get_failover_ip(targets, external, family).await,
+    }?;
+    Ok((ip, source))
 }
@@ -0,0 +1,210 @@
+use std::net::IpAddr;
+
+use anyhow::{anyhow, Result};
+use ipnet::IpNet;
+
+use crate::config::{AddressScope, DnsEntry, IpSource, MetadataAddress};
+
+/// Networks that are never globally routable
+const NON_PUBLIC_NETWORKS: &[&str] = &[
+    "0.0.0.0/8",          // "this" network
+    "10.0.0.0/8",         // RFC 1918
+    "100.64.0.0/10",      // carrier-grade NAT
+    "127.0.0.0/8",        // loopback
+    "169.254.0.0/16",     // link-local
+    "172.16.0.0/12",      // RFC 1918
+    "192.0.0.0/24",       // IETF protocol assignments
+    "192.0.2.0/24",       // documentation
+    "192.168.0.0/16",     // RFC 1918
+    "198.18.0.0/15",      // benchmarking
+    "198.51.100.0/24",    // documentation
+    "203.0.113.0/24",     // documentation
+    "224.0.0.0/4",        // multicast
+    "240.0.0.0/4",        // reserved and broadcast
+    "::/127",             // unspecified and loopback
+    "::ffff:0:0/96",      // IPv4-mapped
+    "100::/64",           // discard
+    "2001:db8::/32",      // documentation
+    "fc00::/7",           // unique local
+    "fe80::/10",          // link-local
+    "ff00::/8",           // multicast
+];
+
+/// Networks accepted for entries that must publish private addresses
+const PRIVATE_NETWORKS: &[&str] = &[
+    "10.0.0.0/8",
+    "100.64.0.0/10",
+    "172.16.0.0/12",
+    "192.168.0.0/16",
+    "fc00::/7",
+];
+
+/// Check that `ip`, produced by `source`, may be published for `entry`.
+/// `source` is the entry's IP source, or the selected target of a failover.
+pub fn check_address(entry: &DnsEntry, ip: &IpAddr, source: &IpSource) -> Result<()> {
+    match entry.record_type.to_uppercase().as_str() {
+        "A" if !ip.is_ipv4() => return Err(anyhow!("{} is not an IPv4 address", ip)),
+        "AAAA" if !ip.is_ipv6() => return Err(anyhow!("{} is not an IPv6 address", ip)),
+        _ => {}
+    }
+
+    let policy = &entry.policy;
+
+    if let Some(net) = policy.deny.iter().find(|net| net.contains(ip)) {
+        return Err(anyhow!("{} is in denied network {}", ip, net));
+    }
+
+    if !policy.allow.is_empty() && !policy.allow.iter().any(|net| net.contains(ip)) {
+        return Err(anyhow!("{} is not in any allowed network", ip));
+    }
+
+    let scope = policy
+        .scope
+        .unwrap_or_else(|| default_scope(source));
+    let in_scope = match scope {
+        AddressScope::Public => !in_networks(NON_PUBLIC_NETWORKS, ip),
+        AddressScope::Private => in_networks(PRIVATE_NETWORKS, ip),
+        AddressScope::Any => true,
+    };
+    if !in_scope {
+        return Err(anyhow!("{} is not a {} address", ip, scope));
+    }
+
+    Ok(())
+}
+
+/// The scope an IP source is expected to produce. A failover has none of its
+/// own, its address is checked against the target that produced it.
+pub fn default_scope(source: &IpSource) -> AddressScope {
+    match source {
+        IpSource::External | IpSource::Prefix(_) => AddressScope::Public,
+        IpSource::Internal(_) => AddressScope::Private,
+        IpSource::Metadata(metadata) => match metadata.address {
+            MetadataAddress::Public => AddressScope::Public,
+            MetadataAddress::Private => AddressScope::Private,
+        },
+        IpSource::Command(_)
+        | IpSource::Static(_)
+        | IpSource::File(_)
+        | IpSource::Failover(_) => AddressScope::Any,
+    }
+}
+
+fn in_networks(networks: &[&str], ip: &IpAddr) -> bool {
+    networks
+        .iter()
+        .filter_map(|net| net.parse::<IpNet>().ok())
+        .any(|net| net.contains(ip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AddressPolicy, FailoverTarget, InternalSelector};
+
+    fn entry(record_type: &str, ip_source: IpSource, policy: AddressPolicy) -> DnsEntry {
+        DnsEntry {
+            provider: "godaddy".to_string(),
+            credentials: None,
+            domain: "example.com".to_string(),
+            record_name: "@".to_string(),
+            record_type: record_type.to_string(),
+            ip_source,
+            ttl: None,
+            policy,
+            damping: None,
+            on_change: None,
+            on_failure: None,
+        }
+    }
+
+    fn check(entry: &DnsEntry, ip: &str) -> bool {
+        check_address(entry, &ip.parse().unwrap(), &entry.ip_source).is_ok()
+    }
+
+    #[test]
+    fn test_networks_parse() {
+        for net in NON_PUBLIC_NETWORKS.iter().chain(PRIVATE_NETWORKS) {
+            assert!(net.parse::<IpNet>().is_ok(), "Invalid network: {}", net);
+        }
+    }
+
+    #[test]
+    fn test_external_rejects_bogons() {
+        let external = entry("A", IpSource::External, AddressPolicy::default());
+        assert!(check(&external, "8.8.8.8"));
+        assert!(!check(&external, "100.64.12.34"));
+        assert!(!check(&external, "192.168.1.10"));
+        assert!(!check(&external, "127.0.0.1"));
+        assert!(!check(&external, "169.254.1.1"));
+        assert!(!check(&external, "203.0.113.5"));
+
+        let external_v6 = entry("AAAA", IpSource::External, AddressPolicy::default());
+        assert!(check(&external_v6, "2606:4700:4700::1111"));
+        assert!(!check(&external_v6, "fd00::1"));
+        assert!(!check(&external_v6, "2001:db8::1"));
+    }
+
+    #[test]
+    fn test_internal_requires_private() {
+        let internal = entry(
+            "A",
+            IpSource::Internal(InternalSelector::default()),
+            AddressPolicy::default(),
+        );
+        assert!(check(&internal, "192.168.1.10"));
+        assert!(check(&internal, "10.1.2.3"));
+        assert!(!check(&internal, "8.8.8.8"));
+        assert!(!check(&internal, "127.0.0.1"));
+    }
+
+    #[test]
+    fn test_failover_uses_selected_target_scope() {
+        let target = |ip_source| FailoverTarget {
+            ip_source,
+            health_check: None,
+        };
+        let failover = entry(
+            "A",
+            IpSource::Failover(vec![
+                target(IpSource::External),
+                target(IpSource::Internal(InternalSelector::default())),
+            ]),
+            AddressPolicy::default(),
+        );
+        let IpSource::Failover(targets) = &failover.ip_source else {
+            unreachable!()
+        };
+        let check_target = |ip: &str, index: usize| {
+            check_address(&failover, &ip.parse().unwrap(), &targets[index].ip_source).is_ok()
+        };
+
+        assert!(check_target("8.8.8.8", 0));
+        assert!(!check_target("192.168.1.10", 0));
+        assert!(check_target("192.168.1.10", 1));
+        assert!(!check_target("8.8.8.8", 1));
+    }
+
+    #[test]
+    fn test_record_type_family() {
+        let static_entry = entry(
+            "AAAA",
+            IpSource::Static("8.8.8.8".parse().unwrap()),
+            AddressPolicy::default(),
+        );
+        assert!(!check(&static_entry, "8.8.8.8"));
+    }
+
+    #[test]
+    fn test_allow_and_deny_lists() {
+        let policy = AddressPolicy {
+            scope: Some(AddressScope::Any),
+            allow: vec!["100.64.0.0/10".parse().unwrap()],
+            deny: vec!["100.64.99.0/24".parse().unwrap()],
+        };
+        let external = entry("A", IpSource::External, policy);
+        assert!(check(&external, "100.64.1.1"));
+        assert!(!check(&external, "100.64.99.1"));
+        assert!(!check(&external, "8.8.8.8"));
+    }
+}
@@ -0,0 +1,78 @@
+use std::net::{IpAddr, Ipv6Addr};
+
+use anyhow::{anyhow, Result};
+
+use super::{get_external_ip, get_internal_ip};
+use crate::config::{
+    AddressFamily, ExternalIpConfig, InternalSelector, Ipv6Scope, PrefixOrigin, PrefixSource,
+};
+
+pub async fn get_prefix_ip(source: &PrefixSource, external: &ExternalIpConfig) -> Result<IpAddr> {
+    if source.prefix_length > 128 {
+        return Err(anyhow!("Invalid IPv6 prefix length: {}", source.prefix_length));
+    }
+
+    let current = match source.from {
+        PrefixOrigin::Interface => {
+            let selector = InternalSelector {
+                interface: source.interface.clone(),
+                ipv6_scope: Ipv6Scope::Global,
+                ..Default::default()
+            };
+            get_internal_ip(&selector, AddressFamily::Ipv6)?
+        }
+        PrefixOrigin::External => get_external_ip(external, AddressFamily::Ipv6).await?,
+    };
+
+    let IpAddr::V6(current) = current else {
+        return Err(anyhow!("Expected an IPv6 address to take the prefix from, got {}", current));
+    };
+    if !is_global(&current) {
+        return Err(anyhow!("No global IPv6 address to take the prefix from, got {}", current));
+    }
+
+    Ok(IpAddr::V6(combine(current, source.suffix, source.prefix_length)))
+}
+
+/// Keep the first `prefix_length` bits of `prefix` and the rest from `suffix`
+fn combine(prefix: Ipv6Addr, suffix: Ipv6Addr, prefix_length: u8) -> Ipv6Addr {
+    let mask = u128::MAX
+        .checked_shl(128 - u32::from(prefix_length))
+        .unwrap_or(0);
+    Ipv6Addr::from((u128::from(prefix) & mask) | (u128::from(suffix) & !mask))
+}
+
+fn is_global(ip: &Ipv6Addr) -> bool {
+    // Global unicast is 2000::/3
+    ip.segments()[0] & 0xe000 == 0x2000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v6(s: &str) -> Ipv6Addr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_combine() {
+        assert_eq!(
+            combine(v6("2001:db8:1:2:aaaa:bbbb:cccc:dddd"), v6("::10"), 64),
+            v6("2001:db8:1:2::10")
+        );
+        assert_eq!(
+            combine(v6("2001:db8:1:2ff::1"), v6("::1:0:0:0:10"), 56),
+            v6("2001:db8:1:201::10")
+        );
+        assert_eq!(combine(v6("2001:db8::1"), v6("::10"), 0), v6("::10"));
+        assert_eq!(combine(v6("2001:db8::1"), v6("::10"), 128), v6("2001:db8::1"));
+    }
+
+    #[test]
+    fn test_is_global() {
+        assert!(is_global(&v6("2001:db8::1")));
+        assert!(!is_global(&v6("fd00::1")));
+        assert!(!is_global(&v6("fe80::1")));
+    }
+}
@@ -1,5 +1,9 @@
 pub mod config;
 pub mod daemon;
 pub mod dns;
+pub mod history;
+pub mod import;
+pub mod init;
 pub mod ip;
+pub mod notify;
 pub mod secrets;
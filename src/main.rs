@@ -1,15 +1,29 @@
+use std::path::{Path, PathBuf};
+
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use tracing::info;
+use tracing::{info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
-use ipdnsd::{config::Settings, daemon, dns::create_provider, ip, secrets};
+use ipdnsd::{
+    config::{
+        config_schema, drop_in_files, env_overrides, AddressFamily, CredentialBackend, Settings,
+        CONFIG_ENV,
+    },
+    daemon,
+    dns::{create_provider, Credentials},
+    history, import, init, ip, secrets,
+};
 
 #[derive(Parser)]
 #[command(name = "ipdnsd")]
 #[command(about = "IP to DNS Updater - monitors IP addresses and updates DNS records")]
 #[command(version)]
 struct Cli {
+    /// Configuration file (default: $IPDNSD_CONFIG, then the user and system config directories)
+    #[arg(long, short = 'c', global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -22,20 +36,82 @@ enum Commands {
     /// Check current IPs and DNS records
     Check,
 
+    /// Create a configuration file interactively
+    Init,
+
+    /// Convert a ddclient or inadyn configuration
+    Import {
+        /// Client the configuration belongs to
+        #[arg(long, value_enum)]
+        from: ImportFrom,
+
+        /// Configuration file to convert, e.g. /etc/ddclient.conf
+        path: PathBuf,
+
+        /// Print the converted configuration instead of writing it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Overwrite an existing configuration file and stored credentials
+        #[arg(long)]
+        force: bool,
+    },
+
     /// Store API credentials for a DNS provider
     SetKey {
         /// DNS provider name (e.g., godaddy)
         provider: String,
+
+        /// Store a named credential profile, used by entries with `credentials = "<name>"`
+        #[arg(long)]
+        profile: Option<String>,
     },
 
     /// Delete stored API credentials for a DNS provider
     DeleteKey {
         /// DNS provider name (e.g., godaddy)
         provider: String,
+
+        /// Delete a named credential profile instead of the default credentials
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// Copy stored API credentials from one credential store to another
+    MigrateKeys {
+        /// Store to copy from
+        #[arg(long, value_enum)]
+        from: StoreArg,
+
+        /// Store to copy to
+        #[arg(long, value_enum)]
+        to: StoreArg,
+
+        /// Delete the credentials from the source store once all were copied
+        #[arg(long)]
+        delete: bool,
     },
 
     /// Show configuration file location and contents
-    Config,
+    Config {
+        #[command(subcommand)]
+        command: Option<ConfigCommand>,
+    },
+
+    /// Show recorded address changes and DNS updates
+    History {
+        /// Only show this entry, e.g. www.example.com
+        #[arg(long)]
+        entry: Option<String>,
+
+        /// Only show records newer than this age, e.g. 30m, 12h, 7d
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Print records as JSON Lines
+        #[arg(long)]
+        json: bool,
+    },
 
     /// Install as a system service
     Install,
@@ -44,6 +120,45 @@ enum Commands {
     Uninstall,
 }
 
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Check the configuration file and stored credentials, reporting every problem found
+    Validate,
+
+    /// Print the JSON Schema of the configuration file
+    Schema,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ImportFrom {
+    Ddclient,
+    Inadyn,
+}
+
+impl From<ImportFrom> for import::Format {
+    fn from(from: ImportFrom) -> Self {
+        match from {
+            ImportFrom::Ddclient => import::Format::Ddclient,
+            ImportFrom::Inadyn => import::Format::Inadyn,
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum StoreArg {
+    File,
+    SecretService,
+}
+
+impl From<StoreArg> for CredentialBackend {
+    fn from(store: StoreArg) -> Self {
+        match store {
+            StoreArg::File => CredentialBackend::File,
+            StoreArg::SecretService => CredentialBackend::SecretService,
+        }
+    }
+}
+
 fn init_logging(log_level: &str) {
     let filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new(log_level));
@@ -59,7 +174,18 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     // Load config for commands that need it
-    let settings = Settings::load().ok();
+    let config_path = Settings::resolve_config_path(cli.config.as_deref());
+    let loaded = Settings::load_from(&config_path);
+    let has_config = config_path.exists() || !env_overrides().is_empty();
+    // A missing file is fine for some commands, an invalid one never is
+    let config_error = match &loaded {
+        Err(e) if has_config => Some(format!("{:#}", e)),
+        _ => None,
+    };
+    let settings = loaded.ok();
+
+    // Credentials are kept in the store the configuration selects
+    let backend = credential_backend(&settings, config_error.as_deref());
 
     // Initialize logging with config level or default
     let log_level = settings
@@ -68,22 +194,58 @@ async fn main() -> Result<()> {
         .unwrap_or("info");
     init_logging(log_level);
 
+    let needs_config = !matches!(
+        cli.command,
+        Commands::Daemon
+            | Commands::Init
+            | Commands::Import { .. }
+            | Commands::Config { .. }
+            | Commands::SetKey { .. }
+            | Commands::DeleteKey { .. }
+            | Commands::MigrateKeys { .. }
+    );
+    if let (Some(error), true) = (&config_error, needs_config) {
+        warn!("Ignoring configuration: {}", error);
+    }
+
     match cli.command {
         Commands::Daemon => {
-            let settings = settings.ok_or_else(|| {
-                anyhow::anyhow!("Configuration file not found. Run 'ipdnsd config' to see the expected location.")
-            })?;
+            if !has_config {
+                anyhow::bail!(
+                    "Configuration file not found: {}. Run 'ipdnsd config' to see the search locations.",
+                    config_path.display()
+                );
+            }
+            let stored = secrets::open_store(backend?)?.list()?;
+            let settings = Settings::load_checked(&config_path, &has_credentials(&stored))?;
             info!("Starting ipdnsd daemon");
             daemon::run(settings).await?;
         }
 
         Commands::Check => {
-            check_status().await?;
+            check_status(&settings).await?;
+        }
+
+        Commands::Init => {
+            init::run(&config_path, secrets::open_store(backend?)?.as_ref()).await?;
         }
 
-        Commands::SetKey { provider } => {
+        Commands::Import {
+            from,
+            path,
+            dry_run,
+            force,
+        } => {
+            let store = secrets::open_store(backend?)?;
+            import::run(from.into(), &path, &config_path, store.as_ref(), dry_run, force)?;
+        }
+
+        Commands::SetKey { provider, profile } => {
             use std::io::{self, Write};
 
+            secrets::check_profile(profile.as_deref())?;
+            let store = secrets::open_store(backend?)?;
+
             print!("API Key: ");
             io::stdout().flush()?;
             let mut key = String::new();
@@ -92,21 +254,94 @@ async fn main() -> Result<()> {
 
             let secret = rpassword::prompt_password("API Secret: ")?;
 
-            secrets::store_credentials(&provider, key, &secret)?;
-            println!("Credentials stored for provider: {}", provider);
+            let profile = profile.as_deref();
+            let credentials = Credentials {
+                api_key: key.to_string(),
+                api_secret: secret,
+            };
+            store.store(&provider, profile, &credentials)?;
+            println!(
+                "Credentials stored for provider {} in {}",
+                secrets::describe(&provider, profile),
+                store.location()
+            );
+        }
+
+        Commands::DeleteKey { provider, profile } => {
+            let profile = profile.as_deref();
+            if !secrets::open_store(backend?)?.delete(&provider, profile)? {
+                anyhow::bail!(
+                    "No credentials found for provider: {}",
+                    secrets::describe(&provider, profile)
+                );
+            }
+            println!(
+                "Credentials deleted for provider: {}",
+                secrets::describe(&provider, profile)
+            );
         }
 
-        Commands::DeleteKey { provider } => {
-            secrets::delete_credentials(&provider)?;
-            println!("Credentials deleted for provider: {}", provider);
+        Commands::MigrateKeys { from, to, delete } => {
+            let (from, to): (CredentialBackend, CredentialBackend) = (from.into(), to.into());
+            if from == to {
+                anyhow::bail!("--from and --to are the same store");
+            }
+            let source = secrets::open_store(from)?;
+            let target = secrets::open_store(to)?;
+
+            let migrated = secrets::migrate_credentials(source.as_ref(), target.as_ref(), delete)?;
+            if migrated.is_empty() {
+                println!("No credentials stored in {}", source.location());
+            } else {
+                for name in &migrated {
+                    println!("Copied credentials for provider: {}", name);
+                }
+                if delete {
+                    println!("Deleted them from {}", source.location());
+                }
+            }
+            if backend.ok() != Some(to) {
+                println!(
+                    "\nSet the store in {} to use them:\n\n[credentials]\nstore = \"{}\"",
+                    config_path.display(),
+                    to
+                );
+            }
         }
 
-        Commands::Config => {
-            show_config(&settings)?;
+        Commands::Config { command: None } => {
+            show_config(&config_path, &settings, config_error.as_deref())?;
+        }
+
+        Commands::Config {
+            command: Some(ConfigCommand::Schema),
+        } => {
+            println!("{}", serde_json::to_string_pretty(&config_schema())?);
+        }
+
+        Commands::Config {
+            command: Some(ConfigCommand::Validate),
+        } => {
+            let stored = secrets::open_store(backend?)?.list()?;
+            let settings = Settings::load_checked(&config_path, &has_credentials(&stored))?;
+            println!(
+                "Configuration file {} is valid ({} DNS entries)",
+                config_path.display(),
+                settings.dns_entries.len()
+            );
+        }
+
+        Commands::History { entry, since, json } => {
+            show_history(&settings, entry.as_deref(), since.as_deref(), json)?;
         }
 
         Commands::Install => {
-            daemon::install_service()?;
+            // The service should use the same config file as this invocation
+            let config = match &cli.config {
+                Some(path) => Some(std::path::absolute(path)?),
+                None => None,
+            };
+            daemon::install_service(config.as_deref())?;
             println!("Service installed successfully");
         }
 
@@ -119,27 +354,62 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-async fn check_status() -> Result<()> {
+/// The credential store the configuration selects. An invalid configuration
+/// may select any store, so the default one is only used when there is no
+/// configuration at all.
+fn credential_backend(
+    settings: &Option<Settings>,
+    config_error: Option<&str>,
+) -> Result<CredentialBackend> {
+    match (settings, config_error) {
+        (Some(settings), _) => Ok(settings.credentials.store),
+        (None, Some(error)) => anyhow::bail!(
+            "Cannot tell which credential store to use, the configuration is invalid: {}",
+            error
+        ),
+        (None, None) => Ok(CredentialBackend::default()),
+    }
+}
+
+/// Credential check for validation, given what the store holds
+fn has_credentials(
+    stored: &[(String, Option<String>)],
+) -> impl Fn(&str, Option<&str>) -> bool + '_ {
+    move |provider, profile| {
+        stored
+            .iter()
+            .any(|(p, stored_profile)| p == provider && stored_profile.as_deref() == profile)
+    }
+}
+
+async fn check_status(settings: &Option<Settings>) -> Result<()> {
     println!("Checking IP addresses...\n");
 
+    let external = settings
+        .as_ref()
+        .map(|s| s.external_ip.clone())
+        .unwrap_or_default();
+
     // Check external IP
-    match ip::get_external_ip().await {
+    match ip::get_external_ip(&external, AddressFamily::Any).await {
         Ok(ip) => println!("External IP: {}", ip),
         Err(e) => println!("External IP: Error - {}", e),
     }
 
     // Check internal IP
-    match ip::get_internal_ip() {
+    match ip::get_internal_ip(&Default::default(), AddressFamily::Any) {
         Ok(ip) => println!("Internal IP: {}", ip),
         Err(e) => println!("Internal IP: Error - {}", e),
     }
 
     // If we have a config, check DNS records
-    if let Ok(settings) = Settings::load() {
+    if let Some(settings) = settings {
         println!("\nChecking DNS records...\n");
 
         for entry in &settings.dns_entries {
-            let creds = match secrets::get_credentials(&entry.provider) {
+            let creds = match secrets::open_store(settings.credentials.store)
+                .and_then(|store| store.credentials(&entry.provider, entry.credentials.as_deref()))
+            {
                 Ok(c) => c,
                 Err(e) => {
                     println!(
@@ -181,19 +451,86 @@ async fn check_status() -> Result<()> {
     Ok(())
 }
 
-fn show_config(settings: &Option<Settings>) -> Result<()> {
-    let config_path = Settings::config_path();
+fn show_history(
+    settings: &Option<Settings>,
+    entry: Option<&str>,
+    since: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let config = settings
+        .as_ref()
+        .map(|s| s.history.clone())
+        .unwrap_or_default()
+        .for_reading();
+    let since = since
+        .map(history::parse_age)
+        .transpose()?
+        .map(|age| chrono::Utc::now() - age);
+
+    let records: Vec<_> = history::read_history(&config)?
+        .into_iter()
+        .filter(|record| entry.is_none_or(|entry| record.entry.eq_ignore_ascii_case(entry)))
+        .filter(|record| since.is_none_or(|since| record.timestamp >= since))
+        .collect();
+
+    if json {
+        for record in &records {
+            println!("{}", serde_json::to_string(record)?);
+        }
+    } else if records.is_empty() {
+        println!("No history recorded in {}", config.path().display());
+    } else {
+        for line in history::format_records(&records) {
+            println!("{}", line);
+        }
+    }
+
+    Ok(())
+}
 
+fn show_config(
+    config_path: &Path,
+    settings: &Option<Settings>,
+    error: Option<&str>,
+) -> Result<()> {
     println!("Configuration file location: {}\n", config_path.display());
 
-    match settings {
-        Some(s) => {
+    let content = std::fs::read_to_string(config_path).unwrap_or_default();
+    let drop_ins = drop_in_files(config_path, &content).unwrap_or_default();
+    if !drop_ins.is_empty() {
+        println!("Drop-in files:");
+        for path in &drop_ins {
+            println!("  {}", path.display());
+        }
+        println!();
+    }
+
+    let overrides = env_overrides();
+    if !overrides.is_empty() {
+        println!("Environment overrides:");
+        for (name, _) in &overrides {
+            println!("  {}", name);
+        }
+        println!();
+    }
+
+    match (settings, error) {
+        (Some(s), _) => {
             println!("Current configuration:\n");
-            println!("{}", toml::to_string_pretty(s)?);
+            println!("{}", toml::to_string_pretty(&s.redacted())?);
         }
-        None => {
+        (None, Some(error)) => {
+            println!("{}", error);
+        }
+        (None, None) => {
             println!("Configuration file not found.");
-            println!("\nCreate a configuration file at the location above.");
+            println!("\nSearched, in order:");
+            println!("  --config <PATH>");
+            println!("  ${}", CONFIG_ENV);
+            for path in Settings::search_paths() {
+                println!("  {}", path.display());
+            }
+            println!("\nCreate a configuration file at one of these locations.");
             println!("Example configuration:\n");
             println!(
                 r#"[daemon]
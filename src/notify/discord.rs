@@ -0,0 +1,79 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+
+use super::event::Event;
+use super::notifier::{http_client, send_request, Notifier};
+use crate::config::DiscordConfig;
+
+/// Discord channel webhook
+pub struct DiscordNotifier {
+    client: Client,
+    config: DiscordConfig,
+}
+
+impl DiscordNotifier {
+    pub fn new(config: DiscordConfig) -> Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            config,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    async fn send(&self, event: &Event) -> Result<()> {
+        let mut body = json!({
+            "content": format!("**{}**\n{}", event.title(), event.message()),
+        });
+        if let Some(username) = &self.config.username {
+            body["username"] = json!(username);
+        }
+
+        send_request(
+            "Discord",
+            self.client.post(&self.config.webhook_url).json(&body),
+        )
+        .await
+    }
+
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EventKind;
+    use crate::notify::event::tests::test_event;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_send() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/webhooks/1/token"))
+            .and(body_json(json!({
+                "content": "**DNS record updated**\nUpdated www.example.com (A) from 198.51.100.1 to 198.51.100.2",
+                "username": "ipdnsd",
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = DiscordNotifier::new(DiscordConfig {
+            webhook_url: format!("{}/api/webhooks/1/token", server.uri()),
+            username: Some("ipdnsd".to_string()),
+        })
+        .unwrap();
+        notifier
+            .send(&test_event(EventKind::RecordUpdated))
+            .await
+            .unwrap();
+    }
+}
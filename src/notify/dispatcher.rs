@@ -0,0 +1,172 @@
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use tracing::{debug, error, warn};
+
+use super::create_notifier;
+use super::event::Event;
+use super::notifier::Notifier;
+use super::throttle::Throttle;
+use crate::config::NotificationConfig;
+
+/// Fans events out to the configured notification targets
+#[derive(Clone, Default)]
+pub struct Dispatcher {
+    targets: Vec<Target>,
+}
+
+#[derive(Clone)]
+struct Target {
+    config: NotificationConfig,
+    notifier: Arc<dyn Notifier>,
+    throttle: Arc<Mutex<Throttle>>,
+}
+
+impl Dispatcher {
+    /// Create notifiers for all targets; targets that fail to initialize are logged and skipped
+    pub fn new(configs: &[NotificationConfig]) -> Self {
+        let targets = configs
+            .iter()
+            .filter_map(|config| match create_notifier(config) {
+                Ok(notifier) => Some(Target {
+                    config: config.clone(),
+                    notifier,
+                    throttle: Default::default(),
+                }),
+                Err(e) => {
+                    error!("Failed to create notification target: {:#}", e);
+                    None
+                }
+            })
+            .collect();
+
+        Self { targets }
+    }
+
+    /// Send an event in the background without delaying the caller
+    pub fn notify(&self, event: Event) {
+        if self.targets.is_empty() {
+            return;
+        }
+
+        let dispatcher = self.clone();
+        tokio::spawn(async move { dispatcher.send(&event).await });
+    }
+
+    /// Run periodic notifier work in the background
+    pub fn tick(&self) {
+        if self.targets.is_empty() {
+            return;
+        }
+
+        let dispatcher = self.clone();
+        tokio::spawn(async move {
+            for target in &dispatcher.targets {
+                if let Err(e) = target.notifier.tick().await {
+                    warn!("Notification target {} failed: {:#}", target.notifier.name(), e);
+                }
+            }
+        });
+    }
+
+    /// Send an event to every interested target and wait for delivery.
+    /// Repeated and excess events are dropped per the target's limits.
+    pub async fn send(&self, event: &Event) {
+        for target in &self.targets {
+            if !target.config.wants(event.kind) {
+                continue;
+            }
+            let allowed = !target.notifier.throttled(event)
+                || target
+                    .throttle
+                    .lock()
+                    .expect("throttle lock poisoned")
+                    .allow(event, Instant::now(), &target.config);
+            if !allowed {
+                debug!(
+                    "Suppressed {} notification via {}",
+                    event.kind,
+                    target.notifier.name()
+                );
+                continue;
+            }
+            if let Err(e) = target.notifier.send(event).await {
+                warn!(
+                    "Failed to send {} notification via {}: {:#}",
+                    event.kind,
+                    target.notifier.name(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{EmailConfig, EventKind, NotificationTarget, WebhookConfig};
+    use crate::notify::email::tests::{config as email_config, smtp_sink};
+    use crate::notify::event::tests::test_event;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_send_filters_and_dedups_events() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dispatcher = Dispatcher::new(&[NotificationConfig {
+            events: vec![EventKind::UpdateFailed],
+            ..NotificationConfig::new(NotificationTarget::Webhook(WebhookConfig {
+                url: server.uri(),
+                method: "POST".to_string(),
+                headers: Default::default(),
+                body: None,
+                content_type: "application/json".to_string(),
+            }))
+        }]);
+
+        dispatcher.send(&test_event(EventKind::RecordUpdated)).await;
+        dispatcher.send(&test_event(EventKind::UpdateFailed)).await;
+        dispatcher.send(&test_event(EventKind::UpdateFailed)).await;
+    }
+
+    #[tokio::test]
+    async fn test_email_failures_bypass_throttle() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let sink = tokio::spawn(async move {
+            let alert = smtp_sink(&listener).await;
+            let recovered = smtp_sink(&listener).await;
+            (alert, recovered)
+        });
+
+        // Default dedup_seconds and max_per_hour
+        let dispatcher = Dispatcher::new(&[NotificationConfig::new(NotificationTarget::Email(
+            EmailConfig {
+                failing_after_seconds: 1,
+                ..email_config(port)
+            },
+        ))]);
+
+        let failed = test_event(EventKind::UpdateFailed).with_error("timeout");
+        dispatcher.send(&failed).await;
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        dispatcher.send(&failed).await;
+        dispatcher.send(&test_event(EventKind::Recovered)).await;
+
+        let (alert, recovered) = tokio::time::timeout(Duration::from_secs(10), sink)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(alert.contains("Subject: [ipdnsd] DNS update failed"));
+        assert!(recovered.contains("Subject: [ipdnsd] DNS updates recovered"));
+    }
+}
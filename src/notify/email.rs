@@ -0,0 +1,415 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::{Credentials, Mechanism};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use super::event::Event;
+use super::notifier::Notifier;
+use crate::config::{EmailConfig, EventKind, SmtpAuth, SmtpSecurity};
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Email via SMTP.
+///
+/// Record updates are mailed right away. Failures are only mailed once an
+/// entry has been failing for `failing_after_seconds`, followed by a mail when
+/// it recovers. A failure ends whenever the daemon confirms the record, so the
+/// next one starts a new window. With `daily_digest` set, every event is also collected into a
+/// daily summary.
+///
+/// Failures and recoveries bypass the target's throttle: repeated failures
+/// are what the threshold counts, and at most two mails go out per failure.
+pub struct EmailNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    failing_after: Duration,
+    digest_at: Option<NaiveTime>,
+    state: Mutex<EmailState>,
+}
+
+#[derive(Default)]
+struct EmailState {
+    /// Entries whose updates are failing
+    failing: HashMap<String, Failing>,
+    /// Events since the last digest
+    digest: Vec<Event>,
+    last_digest: Option<NaiveDate>,
+}
+
+struct Failing {
+    since: Instant,
+    alerted: bool,
+}
+
+impl EmailNotifier {
+    pub fn new(config: EmailConfig) -> Result<Self> {
+        let mut builder = match config.security {
+            SmtpSecurity::Starttls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+            }
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+            SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+        };
+        if let Some(port) = config.port {
+            builder = builder.port(port);
+        }
+        if let Some(username) = &config.username {
+            builder = builder.credentials(Credentials::new(
+                username.clone(),
+                config.password.clone().unwrap_or_default(),
+            ));
+        }
+        if let Some(auth) = config.auth {
+            builder = builder.authentication(vec![match auth {
+                SmtpAuth::Plain => Mechanism::Plain,
+                SmtpAuth::Login => Mechanism::Login,
+            }]);
+        }
+
+        let from = config
+            .from
+            .parse()
+            .with_context(|| format!("Invalid sender address: {}", config.from))?;
+        let to = config
+            .to
+            .iter()
+            .map(|to| {
+                to.parse()
+                    .with_context(|| format!("Invalid recipient address: {}", to))
+            })
+            .collect::<Result<Vec<Mailbox>>>()?;
+        if to.is_empty() {
+            return Err(anyhow!("Email notifications need at least one recipient"));
+        }
+
+        let digest_at = config
+            .daily_digest
+            .as_deref()
+            .map(|time| {
+                NaiveTime::parse_from_str(time, "%H:%M")
+                    .with_context(|| format!("Invalid daily_digest time (expected HH:MM): {}", time))
+            })
+            .transpose()?;
+
+        // Don't send a digest right after startup if today's is already due
+        let now = Local::now().naive_local();
+        let last_digest = digest_at
+            .filter(|at| now.time() >= *at)
+            .map(|_| now.date());
+
+        Ok(Self {
+            transport: builder.timeout(Some(SMTP_TIMEOUT)).build(),
+            from,
+            to,
+            failing_after: Duration::from_secs(config.failing_after_seconds),
+            digest_at,
+            state: Mutex::new(EmailState {
+                last_digest,
+                ..Default::default()
+            }),
+        })
+    }
+
+    /// Decide whether an event warrants an email right now
+    fn immediate_mail(&self, event: &Event, now: Instant) -> Option<(String, String)> {
+        let mut state = self.state.lock().expect("email state lock poisoned");
+        if self.digest_at.is_some() {
+            state.digest.push(event.clone());
+        }
+
+        let key = format!("{} ({})", event.entry, event.record_type);
+        match event.kind {
+            EventKind::RecordUpdated => {
+                // The record is in sync; an alerted failure ends with its recovery mail
+                if state.failing.get(&key).is_some_and(|failing| !failing.alerted) {
+                    state.failing.remove(&key);
+                }
+                Some((subject(event), describe(event)))
+            }
+            EventKind::UpdateFailed | EventKind::AuthFailed => {
+                let failing = state.failing.entry(key).or_insert(Failing {
+                    since: now,
+                    alerted: false,
+                });
+                let duration = now.saturating_duration_since(failing.since);
+                if failing.alerted || duration < self.failing_after {
+                    return None;
+                }
+                failing.alerted = true;
+
+                let body = format!(
+                    "Updates have been failing for {} minutes.\n\n{}",
+                    duration.as_secs() / 60,
+                    describe(event)
+                );
+                Some((subject(event), body))
+            }
+            EventKind::Recovered => match state.failing.remove(&key) {
+                Some(failing) if failing.alerted => Some((subject(event), describe(event))),
+                _ => None,
+            },
+            EventKind::IpChanged => None,
+        }
+    }
+
+    /// Take the collected events if the daily digest is due
+    fn due_digest(&self, now: NaiveDateTime) -> Option<Vec<Event>> {
+        let at = self.digest_at?;
+        let mut state = self.state.lock().expect("email state lock poisoned");
+        if !digest_due(now, at, state.last_digest) {
+            return None;
+        }
+
+        state.last_digest = Some(now.date());
+        Some(std::mem::take(&mut state.digest))
+    }
+
+    async fn deliver(&self, subject: String, body: String) -> Result<()> {
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN);
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        let message = builder.body(body).context("Failed to build email")?;
+
+        self.transport
+            .send(message)
+            .await
+            .context("Failed to send email")?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    async fn send(&self, event: &Event) -> Result<()> {
+        match self.immediate_mail(event, Instant::now()) {
+            Some((subject, body)) => self.deliver(subject, body).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn tick(&self) -> Result<()> {
+        let Some(events) = self.due_digest(Local::now().naive_local()) else {
+            return Ok(());
+        };
+
+        let subject = format!("[ipdnsd] Daily digest: {} events", events.len());
+        let body = if events.is_empty() {
+            "No records changed and no updates failed since the last digest.".to_string()
+        } else {
+            events
+                .iter()
+                .map(|event| format!("{}  {}", event.timestamp.to_rfc3339(), event.message()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        self.deliver(subject, body).await
+    }
+
+    fn throttled(&self, event: &Event) -> bool {
+        !matches!(
+            event.kind,
+            EventKind::UpdateFailed | EventKind::AuthFailed | EventKind::Recovered
+        )
+    }
+
+    fn name(&self) -> &'static str {
+        "email"
+    }
+}
+
+fn subject(event: &Event) -> String {
+    format!("[ipdnsd] {}: {}", event.title(), event.entry)
+}
+
+/// Plain text summary of an event
+fn describe(event: &Event) -> String {
+    let ip = |ip: Option<std::net::IpAddr>| ip.map(|ip| ip.to_string()).unwrap_or_default();
+
+    let mut body = format!(
+        "{}\n\nRecord:   {} ({})\nProvider: {}\nOld IP:   {}\nNew IP:   {}\n",
+        event.message(),
+        event.entry,
+        event.record_type,
+        event.provider,
+        ip(event.old_ip),
+        ip(event.new_ip)
+    );
+    if let Some(error) = &event.error {
+        body.push_str(&format!("Error:    {}\n", error));
+    }
+    body.push_str(&format!("Time:     {}\n", event.timestamp.to_rfc3339()));
+
+    body
+}
+
+/// Whether the digest for `now`'s day is due and hasn't been sent yet
+fn digest_due(now: NaiveDateTime, at: NaiveTime, last: Option<NaiveDate>) -> bool {
+    now.time() >= at && last != Some(now.date())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::notify::event::tests::test_event;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    pub(crate) fn config(port: u16) -> EmailConfig {
+        EmailConfig {
+            host: "127.0.0.1".to_string(),
+            port: Some(port),
+            security: SmtpSecurity::None,
+            username: Some("alerts".to_string()),
+            password: Some("secret".to_string()),
+            auth: Some(SmtpAuth::Plain),
+            from: "ipdnsd <ipdnsd@example.com>".to_string(),
+            to: vec!["ops@example.com".to_string()],
+            failing_after_seconds: 900,
+            daily_digest: None,
+        }
+    }
+
+    /// Accept one SMTP session and return everything the client sent
+    pub(crate) async fn smtp_sink(listener: &TcpListener) -> String {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let mut transcript = String::new();
+        let mut in_data = false;
+
+        writer.write_all(b"220 localhost ESMTP sink\r\n").await.unwrap();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await.unwrap() == 0 {
+                break;
+            }
+            transcript.push_str(&line);
+
+            let reply: &[u8] = if in_data {
+                if line != ".\r\n" {
+                    continue;
+                }
+                in_data = false;
+                b"250 queued\r\n"
+            } else {
+                match line.get(..4).unwrap_or_default().to_uppercase().as_str() {
+                    "EHLO" => b"250-localhost\r\n250 AUTH PLAIN LOGIN\r\n",
+                    "AUTH" => b"235 authenticated\r\n",
+                    "DATA" => {
+                        in_data = true;
+                        b"354 go ahead\r\n"
+                    }
+                    "QUIT" => {
+                        writer.write_all(b"221 bye\r\n").await.unwrap();
+                        break;
+                    }
+                    _ => b"250 ok\r\n",
+                }
+            };
+            writer.write_all(reply).await.unwrap();
+        }
+
+        transcript
+    }
+
+    #[tokio::test]
+    async fn test_send_record_updated() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let sink = tokio::spawn(async move { smtp_sink(&listener).await });
+
+        let notifier = EmailNotifier::new(config(port)).unwrap();
+        notifier
+            .send(&test_event(EventKind::RecordUpdated))
+            .await
+            .unwrap();
+        drop(notifier);
+
+        let transcript = sink.await.unwrap();
+        // "\0alerts\0secret" in base64
+        assert!(transcript.contains("AUTH PLAIN AGFsZXJ0cwBzZWNyZXQ="));
+        assert!(transcript.contains("RCPT TO:<ops@example.com>"));
+        assert!(transcript.contains("Subject: [ipdnsd] DNS record updated: www.example.com"));
+        assert!(transcript.contains("New IP:   198.51.100.2"));
+    }
+
+    #[test]
+    fn test_failure_threshold() {
+        let notifier = EmailNotifier::new(config(25)).unwrap();
+        let start = Instant::now();
+        let failed = test_event(EventKind::UpdateFailed).with_error("timeout");
+
+        assert!(notifier.immediate_mail(&failed, start).is_none());
+        assert!(notifier
+            .immediate_mail(&failed, start + Duration::from_secs(600))
+            .is_none());
+        let (_, body) = notifier
+            .immediate_mail(&failed, start + Duration::from_secs(900))
+            .unwrap();
+        assert!(body.starts_with("Updates have been failing for 15 minutes."));
+        // Only one alert per failure
+        assert!(notifier
+            .immediate_mail(&failed, start + Duration::from_secs(1200))
+            .is_none());
+
+        assert!(notifier
+            .immediate_mail(&test_event(EventKind::Recovered), start)
+            .is_some());
+        assert!(notifier
+            .immediate_mail(&test_event(EventKind::Recovered), start)
+            .is_none());
+    }
+
+    #[test]
+    fn test_failure_ends_when_in_sync() {
+        let notifier = EmailNotifier::new(config(25)).unwrap();
+        let start = Instant::now();
+        let failed = test_event(EventKind::UpdateFailed).with_error("timeout");
+        let later = |seconds| start + Duration::from_secs(seconds);
+
+        // A failure that ends before the threshold, confirmed in sync
+        assert!(notifier.immediate_mail(&failed, start).is_none());
+        assert!(notifier
+            .immediate_mail(&test_event(EventKind::Recovered), later(60))
+            .is_none());
+        // A single new failure hours later isn't mailed
+        assert!(notifier.immediate_mail(&failed, later(7200)).is_none());
+        assert!(notifier.immediate_mail(&failed, later(7800)).is_none());
+
+        // The same after a successful update
+        assert!(notifier
+            .immediate_mail(&test_event(EventKind::RecordUpdated), later(7860))
+            .is_some());
+        assert!(notifier.immediate_mail(&failed, later(20000)).is_none());
+        assert!(notifier.immediate_mail(&failed, later(20900)).is_some());
+    }
+
+    #[test]
+    fn test_digest_due() {
+        let at = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+
+        assert!(!digest_due(day.and_hms_opt(7, 59, 0).unwrap(), at, None));
+        assert!(digest_due(day.and_hms_opt(8, 0, 0).unwrap(), at, None));
+        assert!(!digest_due(day.and_hms_opt(9, 0, 0).unwrap(), at, Some(day)));
+        assert!(digest_due(
+            day.succ_opt().unwrap().and_hms_opt(8, 5, 0).unwrap(),
+            at,
+            Some(day)
+        ));
+    }
+}
@@ -0,0 +1,202 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::config::{DnsEntry, EventKind};
+
+/// An event emitted while checking and updating a DNS entry
+#[derive(Debug, Clone, Serialize)]
+pub struct Event {
+    pub kind: EventKind,
+    pub timestamp: DateTime<Utc>,
+    /// Fully qualified record name, e.g. "www.example.com"
+    pub entry: String,
+    pub domain: String,
+    pub record_name: String,
+    pub record_type: String,
+    pub provider: String,
+    pub old_ip: Option<IpAddr>,
+    pub new_ip: Option<IpAddr>,
+    pub error: Option<String>,
+}
+
+impl Event {
+    pub fn new(kind: EventKind, entry: &DnsEntry) -> Self {
+        Self {
+            kind,
+            timestamp: Utc::now(),
+            entry: entry.fqdn(),
+            domain: entry.domain.clone(),
+            record_name: entry.record_name.clone(),
+            record_type: entry.record_type.clone(),
+            provider: entry.provider.clone(),
+            old_ip: None,
+            new_ip: None,
+            error: None,
+        }
+    }
+
+    pub fn with_ips(mut self, old_ip: Option<IpAddr>, new_ip: IpAddr) -> Self {
+        self.old_ip = old_ip;
+        self.new_ip = Some(new_ip);
+        self
+    }
+
+    pub fn with_error(mut self, error: impl ToString) -> Self {
+        self.error = Some(error.to_string());
+        self
+    }
+
+    /// Short heading for targets that show a title
+    pub fn title(&self) -> &'static str {
+        match self.kind {
+            EventKind::IpChanged => "IP address changed",
+            EventKind::RecordUpdated => "DNS record updated",
+            EventKind::UpdateFailed => "DNS update failed",
+            EventKind::AuthFailed => "DNS provider authentication failed",
+            EventKind::Recovered => "DNS updates recovered",
+        }
+    }
+
+    /// Whether the event reports a problem
+    pub fn is_failure(&self) -> bool {
+        matches!(self.kind, EventKind::UpdateFailed | EventKind::AuthFailed)
+    }
+
+    /// Key identifying repeats of the same event
+    pub fn dedup_key(&self) -> String {
+        format!(
+            "{}|{}|{}|{:?}|{}",
+            self.kind,
+            self.entry,
+            self.record_type,
+            self.new_ip,
+            self.error.as_deref().unwrap_or_default()
+        )
+    }
+
+    /// One-line human readable description
+    pub fn message(&self) -> String {
+        let record = format!("{} ({})", self.entry, self.record_type);
+        let old = self
+            .old_ip
+            .map(|ip| ip.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        let new = self
+            .new_ip
+            .map(|ip| ip.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        let error = self.error.as_deref().unwrap_or("unknown error");
+
+        match self.kind {
+            EventKind::IpChanged => format!("IP for {} changed from {} to {}", record, old, new),
+            EventKind::RecordUpdated => format!("Updated {} from {} to {}", record, old, new),
+            EventKind::UpdateFailed => format!("Failed to update {} to {}: {}", record, new, error),
+            EventKind::AuthFailed => format!(
+                "Authentication with {} failed for {}: {}",
+                self.provider, record, error
+            ),
+            EventKind::Recovered => format!("{} is updating again, now {}", record, new),
+        }
+    }
+
+    /// Value of a template placeholder such as `{{new_ip}}`
+    pub fn field(&self, name: &str) -> Option<String> {
+        let ip = |ip: Option<IpAddr>| ip.map(|ip| ip.to_string()).unwrap_or_default();
+        Some(match name {
+            "event" => self.kind.to_string(),
+            "timestamp" => self.timestamp.to_rfc3339(),
+            "entry" => self.entry.clone(),
+            "domain" => self.domain.clone(),
+            "record_name" => self.record_name.clone(),
+            "record_type" => self.record_type.clone(),
+            "provider" => self.provider.clone(),
+            "old_ip" => ip(self.old_ip),
+            "new_ip" => ip(self.new_ip),
+            "error" => self.error.clone().unwrap_or_default(),
+            "message" => self.message(),
+            _ => return None,
+        })
+    }
+
+    /// Replace `{{placeholder}}`s in `template`, passing values through `escape`
+    pub fn render(&self, template: &str, escape: impl Fn(&str) -> String) -> String {
+        let mut output = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(start) = rest.find("{{") {
+            output.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            match after.find("}}") {
+                Some(end) => {
+                    let name = after[..end].trim();
+                    match self.field(name) {
+                        Some(value) => output.push_str(&escape(&value)),
+                        None => output.push_str(&rest[start..start + 2 + end + 2]),
+                    }
+                    rest = &after[end + 2..];
+                }
+                None => {
+                    output.push_str(&rest[start..]);
+                    rest = "";
+                }
+            }
+        }
+        output.push_str(rest);
+
+        output
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::config::IpSource;
+
+    pub(crate) fn test_entry() -> DnsEntry {
+        DnsEntry {
+            provider: "godaddy".to_string(),
+            credentials: None,
+            domain: "example.com".to_string(),
+            record_name: "www".to_string(),
+            record_type: "A".to_string(),
+            ip_source: IpSource::External,
+            ttl: None,
+            policy: Default::default(),
+            damping: None,
+            on_change: None,
+            on_failure: None,
+        }
+    }
+
+    pub(crate) fn test_event(kind: EventKind) -> Event {
+        Event::new(kind, &test_entry()).with_ips(
+            Some("198.51.100.1".parse().unwrap()),
+            "198.51.100.2".parse().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_message() {
+        assert_eq!(
+            test_event(EventKind::RecordUpdated).message(),
+            "Updated www.example.com (A) from 198.51.100.1 to 198.51.100.2"
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let event = test_event(EventKind::IpChanged);
+        assert_eq!(
+            event.render("{{entry}}: {{ old_ip }} -> {{new_ip}} {{unknown}} {{", |s| s.to_string()),
+            "www.example.com: 198.51.100.1 -> 198.51.100.2 {{unknown}} {{"
+        );
+
+        let event = test_event(EventKind::UpdateFailed).with_error("bad \"quote\"");
+        assert_eq!(
+            event.render(r#"{"error": "{{error}}"}"#, |s| s.replace('"', "\\\"")),
+            r#"{"error": "bad \"quote\""}"#
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+
+use super::event::Event;
+use super::notifier::{http_client, send_request, Notifier};
+use crate::config::GotifyConfig;
+
+/// Gotify server application
+pub struct GotifyNotifier {
+    client: Client,
+    config: GotifyConfig,
+}
+
+impl GotifyNotifier {
+    pub fn new(config: GotifyConfig) -> Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            config,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for GotifyNotifier {
+    async fn send(&self, event: &Event) -> Result<()> {
+        let url = format!("{}/message", self.config.url.trim_end_matches('/'));
+        let priority = if event.is_failure() { 8 } else { 5 };
+        let body = json!({
+            "title": event.title(),
+            "message": event.message(),
+            "priority": self.config.priority.unwrap_or(priority),
+        });
+
+        send_request(
+            "Gotify",
+            self.client
+                .post(url)
+                .header("X-Gotify-Key", &self.config.token)
+                .json(&body),
+        )
+        .await
+    }
+
+    fn name(&self) -> &'static str {
+        "gotify"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EventKind;
+    use crate::notify::event::tests::test_event;
+    use wiremock::matchers::{body_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_send() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/message"))
+            .and(header("X-Gotify-Key", "app_token"))
+            .and(body_json(json!({
+                "title": "IP address changed",
+                "message": "IP for www.example.com (A) changed from 198.51.100.1 to 198.51.100.2",
+                "priority": 2,
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = GotifyNotifier::new(GotifyConfig {
+            url: server.uri(),
+            token: "app_token".to_string(),
+            priority: Some(2),
+        })
+        .unwrap();
+        notifier.send(&test_event(EventKind::IpChanged)).await.unwrap();
+    }
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use tracing::debug;
+
+use super::event::Event;
+use crate::config::CommandSpec;
+
+const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Run a hook with the event described in `IPDNSD_*` environment variables
+pub async fn run_hook(hook: &CommandSpec, event: &Event) -> Result<()> {
+    let stdout = hook
+        .run_with_timeout("Hook", HOOK_TIMEOUT, hook_env(event))
+        .await?;
+
+    debug!("Hook {} finished: {}", hook.args[0], stdout.trim());
+    Ok(())
+}
+
+/// Environment variables describing an event
+fn hook_env(event: &Event) -> Vec<(String, String)> {
+    [
+        ("EVENT", "event"),
+        ("TIMESTAMP", "timestamp"),
+        ("ENTRY", "entry"),
+        ("DOMAIN", "domain"),
+        ("RECORD", "record_name"),
+        ("RECORD_TYPE", "record_type"),
+        ("PROVIDER", "provider"),
+        ("OLD_IP", "old_ip"),
+        ("NEW_IP", "new_ip"),
+        ("ERROR", "error"),
+        ("MESSAGE", "message"),
+    ]
+    .into_iter()
+    .map(|(name, field)| {
+        (
+            format!("IPDNSD_{}", name),
+            event.field(field).unwrap_or_default(),
+        )
+    })
+    .collect()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::config::EventKind;
+    use crate::notify::event::tests::test_event;
+
+    fn hook(script: &str) -> CommandSpec {
+        CommandSpec {
+            args: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+            timeout_seconds: Some(5),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_hook_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("env");
+        let script = format!(
+            "echo \"$IPDNSD_EVENT $IPDNSD_ENTRY $IPDNSD_RECORD $IPDNSD_OLD_IP $IPDNSD_NEW_IP\" > {}",
+            out.display()
+        );
+
+        run_hook(&hook(&script), &test_event(EventKind::RecordUpdated))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(out).unwrap().trim(),
+            "record_updated www.example.com www 198.51.100.1 198.51.100.2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_hook_failure() {
+        let event = test_event(EventKind::UpdateFailed);
+        let err = run_hook(&hook("echo broken >&2; exit 3"), &event)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("broken"));
+
+        let mut slow = hook("sleep 5");
+        slow.timeout_seconds = Some(0);
+        assert!(run_hook(&slow, &event).await.is_err());
+    }
+}
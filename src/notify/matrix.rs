@@ -0,0 +1,122 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::{Client, Url};
+use serde_json::json;
+
+use super::event::Event;
+use super::notifier::{http_client, send_request, Notifier};
+use crate::config::MatrixConfig;
+
+/// Matrix room via the client-server API
+pub struct MatrixNotifier {
+    client: Client,
+    config: MatrixConfig,
+    homeserver: Url,
+    /// Makes transaction IDs unique within this process
+    counter: AtomicU64,
+}
+
+impl MatrixNotifier {
+    pub fn new(config: MatrixConfig) -> Result<Self> {
+        let homeserver = Url::parse(&config.homeserver)
+            .with_context(|| format!("Invalid Matrix homeserver URL: {}", config.homeserver))?;
+        if homeserver.cannot_be_a_base() {
+            return Err(anyhow!(
+                "Invalid Matrix homeserver URL: {}",
+                config.homeserver
+            ));
+        }
+
+        Ok(Self {
+            client: http_client()?,
+            config,
+            homeserver,
+            counter: AtomicU64::new(0),
+        })
+    }
+
+    fn send_url(&self) -> Url {
+        let txn_id = format!(
+            "ipdnsd-{}-{}",
+            Utc::now().timestamp_millis(),
+            self.counter.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let mut url = self.homeserver.clone();
+        url.path_segments_mut()
+            .expect("checked in new()")
+            .pop_if_empty()
+            .extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                &self.config.room_id,
+                "send",
+                "m.room.message",
+                &txn_id,
+            ]);
+        url
+    }
+}
+
+#[async_trait]
+impl Notifier for MatrixNotifier {
+    async fn send(&self, event: &Event) -> Result<()> {
+        let body = json!({
+            "msgtype": "m.text",
+            "body": format!("{}: {}", event.title(), event.message()),
+        });
+
+        send_request(
+            "Matrix",
+            self.client
+                .put(self.send_url())
+                .bearer_auth(&self.config.access_token)
+                .json(&body),
+        )
+        .await
+    }
+
+    fn name(&self) -> &'static str {
+        "matrix"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EventKind;
+    use crate::notify::event::tests::test_event;
+    use wiremock::matchers::{body_partial_json, header, method, path_regex};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_send() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path_regex(
+                r"^/_matrix/client/v3/rooms/!room(:|%3A)example\.org/send/m\.room\.message/ipdnsd-\d+-0$",
+            ))
+            .and(header("Authorization", "Bearer syt_token"))
+            .and(body_partial_json(json!({ "msgtype": "m.text" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "event_id": "$1" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = MatrixNotifier::new(MatrixConfig {
+            homeserver: format!("{}/", server.uri()),
+            access_token: "syt_token".to_string(),
+            room_id: "!room:example.org".to_string(),
+        })
+        .unwrap();
+        notifier
+            .send(&test_event(EventKind::RecordUpdated))
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,44 @@
+mod discord;
+mod dispatcher;
+mod email;
+mod event;
+mod gotify;
+mod hook;
+mod matrix;
+mod notifier;
+mod ntfy;
+mod slack;
+mod telegram;
+mod throttle;
+mod webhook;
+
+pub use discord::DiscordNotifier;
+pub use dispatcher::Dispatcher;
+pub use email::EmailNotifier;
+pub use event::Event;
+pub use gotify::GotifyNotifier;
+pub use hook::run_hook;
+pub use matrix::MatrixNotifier;
+pub use notifier::Notifier;
+pub use ntfy::NtfyNotifier;
+pub use slack::SlackNotifier;
+pub use telegram::TelegramNotifier;
+pub use webhook::WebhookNotifier;
+
+use anyhow::Result;
+use std::sync::Arc;
+
+use crate::config::{NotificationConfig, NotificationTarget};
+
+pub fn create_notifier(config: &NotificationConfig) -> Result<Arc<dyn Notifier>> {
+    Ok(match &config.target {
+        NotificationTarget::Webhook(c) => Arc::new(WebhookNotifier::new(c.clone())?),
+        NotificationTarget::Slack(c) => Arc::new(SlackNotifier::new(c.clone())?),
+        NotificationTarget::Discord(c) => Arc::new(DiscordNotifier::new(c.clone())?),
+        NotificationTarget::Ntfy(c) => Arc::new(NtfyNotifier::new(c.clone())?),
+        NotificationTarget::Gotify(c) => Arc::new(GotifyNotifier::new(c.clone())?),
+        NotificationTarget::Telegram(c) => Arc::new(TelegramNotifier::new(c.clone())?),
+        NotificationTarget::Matrix(c) => Arc::new(MatrixNotifier::new(c.clone())?),
+        NotificationTarget::Email(c) => Arc::new(EmailNotifier::new(c.clone())?),
+    })
+}
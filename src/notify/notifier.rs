@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder};
+
+use super::event::Event;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Deliver an event to the notification target
+    async fn send(&self, event: &Event) -> Result<()>;
+
+    /// Periodic housekeeping such as sending digests, called after each check
+    async fn tick(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether the target's de-duplication and rate limit apply to `event`.
+    /// Notifiers that track failures themselves need to see every event.
+    fn throttled(&self, _event: &Event) -> bool {
+        true
+    }
+
+    /// Get the notifier name
+    fn name(&self) -> &'static str;
+}
+
+/// HTTP client shared by the HTTP based notifiers
+pub(super) fn http_client() -> Result<Client> {
+    Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .context("Failed to create HTTP client")
+}
+
+/// Send a request and turn unsuccessful responses into errors.
+///
+/// Errors leave out the request URL, which can contain a token.
+pub(super) async fn send_request(name: &str, request: RequestBuilder) -> Result<()> {
+    let response = request
+        .send()
+        .await
+        .map_err(reqwest::Error::without_url)
+        .with_context(|| format!("Failed to send {} notification", name))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow!("{} error ({}): {}", name, status, body));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+
+use super::event::Event;
+use super::notifier::{http_client, send_request, Notifier};
+use crate::config::NtfyConfig;
+
+/// ntfy topic on ntfy.sh or a self-hosted server
+pub struct NtfyNotifier {
+    client: Client,
+    config: NtfyConfig,
+}
+
+impl NtfyNotifier {
+    pub fn new(config: NtfyConfig) -> Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            config,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for NtfyNotifier {
+    async fn send(&self, event: &Event) -> Result<()> {
+        let url = format!(
+            "{}/{}",
+            self.config.server.trim_end_matches('/'),
+            self.config.topic
+        );
+        let (priority, tags) = if event.is_failure() {
+            (4, "warning")
+        } else {
+            (3, "globe_with_meridians")
+        };
+
+        let mut request = self
+            .client
+            .post(url)
+            .header("Title", event.title())
+            .header("Priority", self.config.priority.unwrap_or(priority).to_string())
+            .header("Tags", tags)
+            .body(event.message());
+        if let Some(token) = &self.config.token {
+            request = request.bearer_auth(token);
+        }
+
+        send_request("ntfy", request).await
+    }
+
+    fn name(&self) -> &'static str {
+        "ntfy"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EventKind;
+    use crate::notify::event::tests::test_event;
+    use wiremock::matchers::{body_string, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_send() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/home-dns"))
+            .and(header("Title", "DNS provider authentication failed"))
+            .and(header("Priority", "4"))
+            .and(header("Authorization", "Bearer tk_secret"))
+            .and(body_string(
+                "Authentication with godaddy failed for www.example.com (A): 401",
+            ))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = NtfyNotifier::new(NtfyConfig {
+            server: format!("{}/", server.uri()),
+            topic: "home-dns".to_string(),
+            token: Some("tk_secret".to_string()),
+            priority: None,
+        })
+        .unwrap();
+        notifier
+            .send(&test_event(EventKind::AuthFailed).with_error("401"))
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+
+use super::event::Event;
+use super::notifier::{http_client, send_request, Notifier};
+use crate::config::SlackConfig;
+
+/// Slack incoming webhook
+pub struct SlackNotifier {
+    client: Client,
+    config: SlackConfig,
+}
+
+impl SlackNotifier {
+    pub fn new(config: SlackConfig) -> Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            config,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    async fn send(&self, event: &Event) -> Result<()> {
+        let emoji = if event.is_failure() {
+            ":warning:"
+        } else {
+            ":globe_with_meridians:"
+        };
+        let body = json!({
+            "text": format!("{} *{}*\n{}", emoji, event.title(), event.message()),
+        });
+
+        send_request(
+            "Slack",
+            self.client.post(&self.config.webhook_url).json(&body),
+        )
+        .await
+    }
+
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EventKind;
+    use crate::notify::event::tests::test_event;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_send() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/services/T000/B000/XXXX"))
+            .and(body_string_contains("*DNS update failed*"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = SlackNotifier::new(SlackConfig {
+            webhook_url: format!("{}/services/T000/B000/XXXX", server.uri()),
+        })
+        .unwrap();
+        notifier
+            .send(&test_event(EventKind::UpdateFailed).with_error("timeout"))
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+
+use super::event::Event;
+use super::notifier::{http_client, send_request, Notifier};
+use crate::config::TelegramConfig;
+
+/// Telegram bot API
+pub struct TelegramNotifier {
+    client: Client,
+    config: TelegramConfig,
+}
+
+impl TelegramNotifier {
+    pub fn new(config: TelegramConfig) -> Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            config,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    async fn send(&self, event: &Event) -> Result<()> {
+        let url = format!(
+            "{}/bot{}/sendMessage",
+            self.config.api_url.trim_end_matches('/'),
+            self.config.bot_token
+        );
+        let body = json!({
+            "chat_id": self.config.chat_id,
+            "text": format!("{}\n{}", event.title(), event.message()),
+            "disable_web_page_preview": true,
+        });
+
+        send_request("Telegram", self.client.post(url).json(&body)).await
+    }
+
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EventKind;
+    use crate::notify::event::tests::test_event;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_send() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:ABC/sendMessage"))
+            .and(body_partial_json(json!({ "chat_id": "-100200300" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = TelegramNotifier::new(TelegramConfig {
+            bot_token: "123:ABC".to_string(),
+            chat_id: "-100200300".to_string(),
+            api_url: server.uri(),
+        })
+        .unwrap();
+        notifier
+            .send(&test_event(EventKind::Recovered))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_error_hides_token() {
+        let notifier = TelegramNotifier::new(TelegramConfig {
+            bot_token: "123:ABC".to_string(),
+            chat_id: "-100200300".to_string(),
+            api_url: "http://127.0.0.1:1".to_string(),
+        })
+        .unwrap();
+        let error = notifier
+            .send(&test_event(EventKind::Recovered))
+            .await
+            .unwrap_err();
+        assert!(!format!("{:#}", error).contains("123:ABC"));
+    }
+}
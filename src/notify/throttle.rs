@@ -0,0 +1,95 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use super::event::Event;
+use crate::config::NotificationConfig;
+
+const HOUR: Duration = Duration::from_secs(3600);
+
+/// Per-target rate limit and de-duplication of repeated events
+#[derive(Debug, Default)]
+pub struct Throttle {
+    /// When each recently sent event key was last sent
+    recent: HashMap<String, Instant>,
+    /// Send times within the last hour
+    sent: VecDeque<Instant>,
+}
+
+impl Throttle {
+    /// Whether `event` may be sent now; records it as sent if so
+    pub fn allow(&mut self, event: &Event, now: Instant, config: &NotificationConfig) -> bool {
+        let window = Duration::from_secs(config.dedup_seconds);
+        self.recent
+            .retain(|_, sent| now.saturating_duration_since(*sent) < window);
+        while self
+            .sent
+            .front()
+            .is_some_and(|sent| now.saturating_duration_since(*sent) >= HOUR)
+        {
+            self.sent.pop_front();
+        }
+
+        let key = event.dedup_key();
+        if self.recent.contains_key(&key) {
+            return false;
+        }
+        if let Some(max) = config.max_per_hour {
+            if self.sent.len() >= max as usize {
+                return false;
+            }
+        }
+
+        if !window.is_zero() {
+            self.recent.insert(key, now);
+        }
+        self.sent.push_back(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{EventKind, NotificationTarget, SlackConfig};
+    use crate::notify::event::tests::test_event;
+
+    fn config(dedup_seconds: u64, max_per_hour: Option<u32>) -> NotificationConfig {
+        NotificationConfig {
+            dedup_seconds,
+            max_per_hour,
+            ..NotificationConfig::new(NotificationTarget::Slack(SlackConfig {
+                webhook_url: "http://localhost".to_string(),
+            }))
+        }
+    }
+
+    #[test]
+    fn test_dedup() {
+        let config = config(60, None);
+        let mut throttle = Throttle::default();
+        let start = Instant::now();
+        let failed = test_event(EventKind::UpdateFailed);
+
+        assert!(throttle.allow(&failed, start, &config));
+        assert!(!throttle.allow(&failed, start + Duration::from_secs(30), &config));
+        assert!(throttle.allow(
+            &test_event(EventKind::RecordUpdated),
+            start + Duration::from_secs(30),
+            &config
+        ));
+        assert!(throttle.allow(&failed, start + Duration::from_secs(61), &config));
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let config = config(0, Some(2));
+        let mut throttle = Throttle::default();
+        let start = Instant::now();
+        let event = test_event(EventKind::IpChanged);
+
+        assert!(throttle.allow(&event, start, &config));
+        assert!(throttle.allow(&event, start + Duration::from_secs(1), &config));
+        assert!(!throttle.allow(&event, start + Duration::from_secs(2), &config));
+        assert!(throttle.allow(&event, start + HOUR, &config));
+    }
+}
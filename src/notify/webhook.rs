@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::{Client, Method};
+
+use super::event::Event;
+use super::notifier::{http_client, send_request, Notifier};
+use crate::config::WebhookConfig;
+
+/// Generic HTTP webhook with a configurable method, headers and body template
+pub struct WebhookNotifier {
+    client: Client,
+    config: WebhookConfig,
+    method: Method,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: WebhookConfig) -> Result<Self> {
+        let method = Method::from_bytes(config.method.to_uppercase().as_bytes())
+            .with_context(|| format!("Invalid HTTP method: {}", config.method))?;
+
+        Ok(Self {
+            client: http_client()?,
+            config,
+            method,
+        })
+    }
+
+    fn body(&self, event: &Event) -> Result<String> {
+        let is_json = self.config.content_type.contains("json");
+
+        match &self.config.body {
+            Some(template) if is_json => Ok(event.render(template, json_escape)),
+            Some(template) => Ok(event.render(template, str::to_string)),
+            None => serde_json::to_string(event).context("Failed to serialize event"),
+        }
+    }
+}
+
+/// Escape a value for use inside a JSON string literal
+fn json_escape(value: &str) -> String {
+    let quoted = serde_json::to_string(value).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn send(&self, event: &Event) -> Result<()> {
+        let mut request = self
+            .client
+            .request(self.method.clone(), &self.config.url)
+            .header("Content-Type", &self.config.content_type);
+
+        for (name, value) in &self.config.headers {
+            request = request.header(name, value);
+        }
+
+        if self.method != Method::GET {
+            request = request.body(self.body(event)?);
+        }
+
+        send_request("Webhook", request).await
+    }
+
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EventKind;
+    use crate::notify::event::tests::test_event;
+    use std::collections::BTreeMap;
+    use wiremock::matchers::{body_json, body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config(server: &MockServer, body: Option<&str>) -> WebhookConfig {
+        WebhookConfig {
+            url: format!("{}/hook", server.uri()),
+            method: "post".to_string(),
+            headers: BTreeMap::from([("X-Token".to_string(), "secret".to_string())]),
+            body: body.map(str::to_string),
+            content_type: "application/json".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_templated_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(header("X-Token", "secret"))
+            .and(body_json(serde_json::json!({
+                "text": "Failed to update www.example.com (A) to 198.51.100.2: \"quoted\"",
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = WebhookNotifier::new(config(&server, Some(r#"{"text": "{{message}}"}"#)))
+            .unwrap();
+        let event = test_event(EventKind::UpdateFailed).with_error("\"quoted\"");
+        notifier.send(&event).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_default_body_and_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_string_contains(r#""kind":"record_updated""#))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let notifier = WebhookNotifier::new(config(&server, None)).unwrap();
+        assert!(notifier
+            .send(&test_event(EventKind::RecordUpdated))
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{provider_key, CredentialStore};
+use crate::config::Settings;
+use crate::dns::Credentials;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CredentialsFile {
+    /// Default credentials by provider
+    #[serde(default)]
+    providers: HashMap<String, ProviderCredentials>,
+    /// Named credential profiles by provider, then profile name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    profiles: HashMap<String, HashMap<String, ProviderCredentials>>,
+}
+
+impl CredentialsFile {
+    fn get(&self, provider: &str, profile: Option<&str>) -> Option<&ProviderCredentials> {
+        let provider = &provider_key(provider);
+        match profile {
+            None => self.providers.get(provider),
+            Some(profile) => self.profiles.get(provider)?.get(profile),
+        }
+    }
+
+    fn insert(&mut self, provider: &str, profile: Option<&str>, creds: ProviderCredentials) {
+        let provider = provider_key(provider);
+        match profile {
+            None => self.providers.insert(provider, creds),
+            Some(profile) => self
+                .profiles
+                .entry(provider)
+                .or_default()
+                .insert(profile.to_string(), creds),
+        };
+    }
+
+    fn remove(&mut self, provider: &str, profile: Option<&str>) -> Option<ProviderCredentials> {
+        let provider = &provider_key(provider);
+        match profile {
+            None => self.providers.remove(provider),
+            Some(profile) => {
+                let profiles = self.profiles.get_mut(provider)?;
+                let removed = profiles.remove(profile);
+                if profiles.is_empty() {
+                    self.profiles.remove(provider);
+                }
+                removed
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ProviderCredentials {
+    api_key: String,
+    api_secret: String,
+}
+
+/// Credentials kept in a TOML file readable only by its owner
+pub struct FileStore {
+    path: PathBuf,
+}
+
+impl Default for FileStore {
+    fn default() -> Self {
+        Self::new(credentials_path())
+    }
+}
+
+impl FileStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn load(&self) -> Result<CredentialsFile> {
+        let path = &self.path;
+        if !path.exists() {
+            return Ok(CredentialsFile::default());
+        }
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read credentials file: {}", path.display()))?;
+
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse credentials file: {}", path.display()))
+    }
+
+    fn save(&self, creds: &CredentialsFile) -> Result<()> {
+        let path = &self.path;
+
+        // Ensure parent directory exists
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+
+        let content = toml::to_string_pretty(creds)
+            .context("Failed to serialize credentials")?;
+
+        fs::write(path, &content)
+            .with_context(|| format!("Failed to write credentials file: {}", path.display()))?;
+
+        // Set restrictive permissions on Unix
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let perms = fs::Permissions::from_mode(0o600);
+            fs::set_permissions(path, perms)
+                .with_context(|| format!("Failed to set permissions on: {}", path.display()))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `credentials.toml` next to the configuration ipdnsd writes: the user's
+/// config directory for unprivileged users, the system-wide one for root
+fn credentials_path() -> PathBuf {
+    Settings::own_config_dir().join("credentials.toml")
+}
+
+impl CredentialStore for FileStore {
+    fn location(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn get(&self, provider: &str, profile: Option<&str>) -> Result<Option<Credentials>> {
+        Ok(self.load()?.get(provider, profile).map(|creds| Credentials {
+            api_key: creds.api_key.clone(),
+            api_secret: creds.api_secret.clone(),
+        }))
+    }
+
+    fn store(&self, provider: &str, profile: Option<&str>, credentials: &Credentials) -> Result<()> {
+        let mut creds_file = self.load()?;
+
+        creds_file.insert(
+            provider,
+            profile,
+            ProviderCredentials {
+                api_key: credentials.api_key.clone(),
+                api_secret: credentials.api_secret.clone(),
+            },
+        );
+
+        self.save(&creds_file)
+    }
+
+    fn delete(&self, provider: &str, profile: Option<&str>) -> Result<bool> {
+        let mut creds_file = self.load()?;
+
+        if creds_file.remove(provider, profile).is_none() {
+            return Ok(false);
+        }
+
+        self.save(&creds_file)?;
+        Ok(true)
+    }
+
+    fn list(&self) -> Result<Vec<(String, Option<String>)>> {
+        let creds_file = self.load()?;
+
+        let mut stored: Vec<_> = creds_file
+            .providers
+            .keys()
+            .map(|provider| (provider.clone(), None))
+            .chain(creds_file.profiles.iter().flat_map(|(provider, profiles)| {
+                profiles
+                    .keys()
+                    .map(move |profile| (provider.clone(), Some(profile.clone())))
+            }))
+            .collect();
+        stored.sort();
+        Ok(stored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles() {
+        let content = r#"
+[providers.godaddy]
+api_key = "default-key"
+api_secret = "default-secret"
+
+[profiles.godaddy.client-a]
+api_key = "a-key"
+api_secret = "a-secret"
+"#;
+        let mut creds: CredentialsFile = toml::from_str(content).unwrap();
+        assert_eq!(creds.get("godaddy", None).unwrap().api_key, "default-key");
+        assert_eq!(creds.get("godaddy", Some("client-a")).unwrap().api_key, "a-key");
+        assert!(creds.get("godaddy", Some("client-b")).is_none());
+
+        assert!(creds.remove("godaddy", Some("client-a")).is_some());
+        assert!(creds.profiles.is_empty());
+        assert!(!toml::to_string(&creds).unwrap().contains("profiles"));
+    }
+}
@@ -1,3 +1,176 @@
-mod keyring_store;
+mod file;
+#[cfg(target_os = "linux")]
+mod secret_service;
 
-pub use keyring_store::{delete_credentials, get_credentials, store_credentials};
+pub use file::FileStore;
+#[cfg(target_os = "linux")]
+pub use secret_service::SecretServiceStore;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::config::CredentialBackend;
+use crate::dns::Credentials;
+
+/// Where API credentials are kept
+pub trait CredentialStore {
+    /// Description of the store for messages, e.g. the file path
+    fn location(&self) -> String;
+
+    /// Credentials of a provider, or of one of its named profiles
+    fn get(&self, provider: &str, profile: Option<&str>) -> Result<Option<Credentials>>;
+
+    fn store(&self, provider: &str, profile: Option<&str>, credentials: &Credentials) -> Result<()>;
+
+    /// Delete credentials, returning whether any were stored
+    fn delete(&self, provider: &str, profile: Option<&str>) -> Result<bool>;
+
+    /// Every provider and profile with stored credentials
+    fn list(&self) -> Result<Vec<(String, Option<String>)>>;
+
+    /// Credentials that must exist, failing with a hint on how to store them
+    fn credentials(&self, provider: &str, profile: Option<&str>) -> Result<Credentials> {
+        self.get(provider, profile)?.ok_or_else(|| {
+            anyhow!(
+                "Credentials not found for provider: {}. Use '{}' to store credentials.",
+                describe(provider, profile),
+                set_key_command(provider, profile)
+            )
+        })
+    }
+}
+
+/// Open the credential store selected in the configuration
+pub fn open_store(backend: CredentialBackend) -> Result<Box<dyn CredentialStore>> {
+    match backend {
+        CredentialBackend::File => Ok(Box::new(FileStore::default())),
+        #[cfg(target_os = "linux")]
+        CredentialBackend::SecretService => Ok(Box::new(SecretServiceStore)),
+        #[cfg(not(target_os = "linux"))]
+        CredentialBackend::SecretService => {
+            bail!("The secret-service credential store is only available on Linux")
+        }
+    }
+}
+
+/// Copy every stored credential from one store to another, optionally
+/// deleting them from the source. Returns what was copied.
+pub fn migrate_credentials(
+    from: &dyn CredentialStore,
+    to: &dyn CredentialStore,
+    delete: bool,
+) -> Result<Vec<String>> {
+    let mut migrated = Vec::new();
+
+    for (provider, profile) in from.list()? {
+        let profile = profile.as_deref();
+        let credentials = from.credentials(&provider, profile)?;
+        to.store(&provider, profile, &credentials)?;
+        migrated.push(describe(&provider, profile));
+    }
+
+    // Only delete once everything was copied
+    if delete {
+        for (provider, profile) in from.list()? {
+            from.delete(&provider, profile.as_deref())?;
+        }
+    }
+
+    Ok(migrated)
+}
+
+/// Key a provider's credentials are stored under. Provider names are case
+/// insensitive, like in the configuration.
+pub fn provider_key(provider: &str) -> String {
+    provider.to_lowercase()
+}
+
+/// Whether `name` can be used as a credential profile name: letters, digits,
+/// `-`, `_` and `.`
+pub fn is_valid_profile(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Fail unless `profile` is a valid profile name
+pub fn check_profile(profile: Option<&str>) -> Result<()> {
+    match profile {
+        Some(profile) if !is_valid_profile(profile) => bail!(
+            "Invalid profile name: {:?}. Use letters, digits, '-', '_' and '.'",
+            profile
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// The `set-key` command line that stores these credentials
+pub fn set_key_command(provider: &str, profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("ipdnsd set-key {} --profile {}", provider, profile),
+        None => format!("ipdnsd set-key {}", provider),
+    }
+}
+
+/// Human readable name of a provider's credentials, e.g. "godaddy (profile client-a)"
+pub fn describe(provider: &str, profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("{} (profile {})", provider, profile),
+        None => provider.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credentials(key: &str) -> Credentials {
+        Credentials {
+            api_key: key.to_string(),
+            api_secret: format!("{}-secret", key),
+        }
+    }
+
+    #[test]
+    fn test_migrate_credentials() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = FileStore::new(dir.path().join("from.toml"));
+        let to = FileStore::new(dir.path().join("to.toml"));
+        from.store("godaddy", None, &credentials("default")).unwrap();
+        from.store("godaddy", Some("client-a"), &credentials("a")).unwrap();
+
+        let migrated = migrate_credentials(&from, &to, true).unwrap();
+        assert_eq!(migrated, ["godaddy", "godaddy (profile client-a)"]);
+        assert_eq!(
+            to.credentials("godaddy", Some("client-a")).unwrap().api_secret,
+            "a-secret"
+        );
+        assert_eq!(to.list().unwrap().len(), 2);
+        assert!(from.list().unwrap().is_empty());
+
+        let missing = to.credentials("godaddy", Some("client-b")).unwrap_err();
+        assert!(missing
+            .to_string()
+            .contains("Use 'ipdnsd set-key godaddy --profile client-b'"));
+    }
+
+    #[test]
+    fn test_provider_names_ignore_case() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::new(dir.path().join("credentials.toml"));
+        store.store("GoDaddy", Some("client-a"), &credentials("a")).unwrap();
+
+        assert_eq!(store.credentials("godaddy", Some("client-a")).unwrap().api_key, "a");
+        assert_eq!(store.list().unwrap(), [("godaddy".to_string(), Some("client-a".to_string()))]);
+        assert!(store.delete("GODADDY", Some("client-a")).unwrap());
+    }
+
+    #[test]
+    fn test_profile_names() {
+        assert!(is_valid_profile("client-a.prod_2"));
+        assert!(!is_valid_profile("client a"));
+        assert!(!is_valid_profile(""));
+        assert!(check_profile(Some("a/b")).is_err());
+        assert!(check_profile(None).is_ok());
+    }
+}
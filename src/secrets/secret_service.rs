@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use secret_service::blocking::{Collection, SecretService};
+use secret_service::EncryptionType;
+use serde::{Deserialize, Serialize};
+
+use super::{describe, provider_key, CredentialStore};
+use crate::dns::Credentials;
+
+/// Attribute marking the items stored by ipdnsd
+const APPLICATION: &str = "ipdnsd";
+
+/// The secret of an item, as JSON
+#[derive(Serialize, Deserialize)]
+struct StoredSecret {
+    api_key: String,
+    api_secret: String,
+}
+
+/// Credentials kept in the default collection of the Secret Service
+/// (GNOME Keyring, KWallet, KeePassXC...) on the D-Bus session bus.
+///
+/// Items carry the attributes `application = "ipdnsd"`, `provider` and
+/// `profile`, which is empty for a provider's default credentials.
+pub struct SecretServiceStore;
+
+impl SecretServiceStore {
+    /// Run `f` with the unlocked default collection
+    fn with_collection<T>(&self, f: impl FnOnce(&Collection) -> Result<T>) -> Result<T> {
+        // Secrets travel encrypted when the service supports it
+        let service = SecretService::connect(EncryptionType::Dh)
+            .or_else(|_| SecretService::connect(EncryptionType::Plain))
+            .context("Failed to connect to the Secret Service on the D-Bus session bus")?;
+        let collection = service
+            .get_default_collection()
+            .context("The Secret Service has no default collection")?;
+        if collection
+            .is_locked()
+            .context("Failed to query the Secret Service collection")?
+        {
+            collection
+                .unlock()
+                .context("Failed to unlock the Secret Service collection")?;
+        }
+
+        f(&collection)
+    }
+}
+
+fn attributes<'a>(provider: &'a str, profile: Option<&'a str>) -> HashMap<&'a str, &'a str> {
+    HashMap::from([
+        ("application", APPLICATION),
+        ("provider", provider),
+        ("profile", profile.unwrap_or_default()),
+    ])
+}
+
+impl CredentialStore for SecretServiceStore {
+    fn location(&self) -> String {
+        "the Secret Service".to_string()
+    }
+
+    fn get(&self, provider: &str, profile: Option<&str>) -> Result<Option<Credentials>> {
+        let key = provider_key(provider);
+        self.with_collection(|collection| {
+            let items = collection.search_items(attributes(&key, profile))?;
+            let Some(item) = items.first() else {
+                return Ok(None);
+            };
+
+            let secret = item.get_secret()?;
+            let stored: StoredSecret = serde_json::from_slice(&secret).with_context(|| {
+                format!(
+                    "Invalid Secret Service item for {}",
+                    describe(provider, profile)
+                )
+            })?;
+            Ok(Some(Credentials {
+                api_key: stored.api_key,
+                api_secret: stored.api_secret,
+            }))
+        })
+    }
+
+    fn store(&self, provider: &str, profile: Option<&str>, credentials: &Credentials) -> Result<()> {
+        let secret = serde_json::to_vec(&StoredSecret {
+            api_key: credentials.api_key.clone(),
+            api_secret: credentials.api_secret.clone(),
+        })?;
+
+        self.with_collection(|collection| {
+            collection
+                .create_item(
+                    &format!("ipdnsd credentials for {}", describe(provider, profile)),
+                    attributes(&provider_key(provider), profile),
+                    &secret,
+                    true,
+                    "application/json",
+                )
+                .context("Failed to store credentials in the Secret Service")?;
+            Ok(())
+        })
+    }
+
+    fn delete(&self, provider: &str, profile: Option<&str>) -> Result<bool> {
+        let key = provider_key(provider);
+        self.with_collection(|collection| {
+            let items = collection.search_items(attributes(&key, profile))?;
+            for item in &items {
+                item.delete()
+                    .context("Failed to delete credentials from the Secret Service")?;
+            }
+            Ok(!items.is_empty())
+        })
+    }
+
+    fn list(&self) -> Result<Vec<(String, Option<String>)>> {
+        self.with_collection(|collection| {
+            let items = collection.search_items(HashMap::from([("application", APPLICATION)]))?;
+
+            let mut stored = Vec::new();
+            for item in items {
+                let mut attributes = item.get_attributes()?;
+                let Some(provider) = attributes.remove("provider") else {
+                    continue;
+                };
+                let profile = attributes.remove("profile").filter(|p| !p.is_empty());
+                stored.push((provider, profile));
+            }
+            stored.sort();
+            Ok(stored)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Runs against a minimal in-process Secret Service on a private
+    //! `dbus-daemon`. Ignored by default, run with `cargo test -- --ignored`
+    //! where `dbus-daemon` is installed
+
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::{Arc, Mutex};
+    use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Type, Value};
+    use zbus::{fdo, interface, ObjectServer};
+
+    const COLLECTION: &str = "/org/freedesktop/secrets/collection/login";
+
+    /// Secrets by item path: attributes and value
+    type Items = Arc<Mutex<BTreeMap<String, (HashMap<String, String>, Vec<u8>)>>>;
+
+    #[derive(Serialize, Deserialize, Type)]
+    struct Secret {
+        session: OwnedObjectPath,
+        parameters: Vec<u8>,
+        value: Vec<u8>,
+        content_type: String,
+    }
+
+    fn path(path: &str) -> OwnedObjectPath {
+        ObjectPath::try_from(path).unwrap().into()
+    }
+
+    fn matching(items: &Items, attributes: &HashMap<String, String>) -> Vec<OwnedObjectPath> {
+        items
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, (item, _))| attributes.iter().all(|(k, v)| item.get(k) == Some(v)))
+            .map(|(item_path, _)| path(item_path))
+            .collect()
+    }
+
+    struct Service;
+
+    #[interface(name = "org.freedesktop.Secret.Service")]
+    impl Service {
+        fn open_session(
+            &self,
+            algorithm: &str,
+            _input: Value<'_>,
+        ) -> fdo::Result<(OwnedValue, OwnedObjectPath)> {
+            if algorithm != "plain" {
+                return Err(fdo::Error::NotSupported(algorithm.to_string()));
+            }
+            let output = OwnedValue::try_from(Value::from("")).unwrap();
+            Ok((output, path("/org/freedesktop/secrets/session/1")))
+        }
+
+        fn read_alias(&self, name: &str) -> OwnedObjectPath {
+            path(if name == "default" { COLLECTION } else { "/" })
+        }
+    }
+
+    struct FakeCollection {
+        items: Items,
+        next: usize,
+    }
+
+    #[interface(name = "org.freedesktop.Secret.Collection")]
+    impl FakeCollection {
+        #[zbus(property)]
+        fn locked(&self) -> bool {
+            false
+        }
+
+        fn search_items(&self, attributes: HashMap<String, String>) -> Vec<OwnedObjectPath> {
+            matching(&self.items, &attributes)
+        }
+
+        async fn create_item(
+            &mut self,
+            properties: HashMap<String, OwnedValue>,
+            secret: Secret,
+            replace: bool,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+            let attributes: HashMap<String, String> = properties
+                .get("org.freedesktop.Secret.Item.Attributes")
+                .and_then(|value| value.try_clone().ok())
+                .and_then(|value| HashMap::try_from(value).ok())
+                .ok_or_else(|| fdo::Error::InvalidArgs("missing attributes".to_string()))?;
+
+            let existing = matching(&self.items, &attributes);
+            let item_path = match existing.first() {
+                Some(existing) if replace => existing.to_string(),
+                _ => {
+                    self.next += 1;
+                    let item_path = format!("{}/{}", COLLECTION, self.next);
+                    let item = FakeItem {
+                        path: item_path.clone(),
+                        items: self.items.clone(),
+                    };
+                    server.at(item_path.as_str(), item).await?;
+                    item_path
+                }
+            };
+            self.items
+                .lock()
+                .unwrap()
+                .insert(item_path.clone(), (attributes, secret.value));
+            Ok((path(&item_path), path("/")))
+        }
+    }
+
+    struct FakeItem {
+        path: String,
+        items: Items,
+    }
+
+    impl FakeItem {
+        fn get(&self) -> fdo::Result<(HashMap<String, String>, Vec<u8>)> {
+            self.items
+                .lock()
+                .unwrap()
+                .get(&self.path)
+                .cloned()
+                .ok_or_else(|| fdo::Error::UnknownObject(self.path.clone()))
+        }
+    }
+
+    #[interface(name = "org.freedesktop.Secret.Item")]
+    impl FakeItem {
+        #[zbus(property)]
+        fn locked(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn attributes(&self) -> fdo::Result<HashMap<String, String>> {
+            Ok(self.get()?.0)
+        }
+
+        fn get_secret(&self, session: OwnedObjectPath) -> fdo::Result<Secret> {
+            Ok(Secret {
+                session,
+                parameters: Vec::new(),
+                value: self.get()?.1,
+                content_type: "application/json".to_string(),
+            })
+        }
+
+        async fn delete(
+            &self,
+            #[zbus(object_server)] server: &ObjectServer,
+        ) -> fdo::Result<OwnedObjectPath> {
+            self.items.lock().unwrap().remove(&self.path);
+            server.remove::<FakeItem, _>(self.path.as_str()).await?;
+            Ok(path("/"))
+        }
+    }
+
+    struct Bus(Child);
+
+    impl Drop for Bus {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    /// Start a private session bus, returning its address
+    fn start_bus(dir: &std::path::Path) -> Option<(Bus, String)> {
+        let config = dir.join("bus.conf");
+        std::fs::write(
+            &config,
+            format!(
+                r#"<busconfig>
+  <type>session</type>
+  <listen>unix:path={}</listen>
+  <auth>EXTERNAL</auth>
+  <policy context="default">
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow receive_sender="*"/>
+    <allow own="*"/>
+  </policy>
+</busconfig>"#,
+                dir.join("bus").display()
+            ),
+        )
+        .unwrap();
+
+        let mut child = Command::new("dbus-daemon")
+            .arg(format!("--config-file={}", config.display()))
+            .args(["--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .ok()?;
+        let mut address = String::new();
+        BufReader::new(child.stdout.take()?)
+            .read_line(&mut address)
+            .ok()?;
+        Some((Bus(child), address.trim().to_string()))
+    }
+
+    /// Set for the child process running `exercise_store` against a private bus
+    const TEST_BUS_ENV: &str = "IPDNSD_TEST_SECRET_SERVICE_BUS";
+
+    // The Secret Service client only connects to the bus named by
+    // `DBUS_SESSION_BUS_ADDRESS`, so the store runs in a child process with
+    // the private bus address in its environment rather than changing the
+    // environment of the test process.
+    #[test]
+    #[ignore = "needs dbus-daemon"]
+    fn test_secret_service_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_bus, address) = start_bus(dir.path()).expect("dbus-daemon not available");
+
+        let items = Items::default();
+        let _service = zbus::blocking::connection::Builder::address(address.as_str())
+            .unwrap()
+            .name("org.freedesktop.secrets")
+            .unwrap()
+            .serve_at("/org/freedesktop/secrets", Service)
+            .unwrap()
+            .serve_at(COLLECTION, FakeCollection { items: items.clone(), next: 0 })
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let status = Command::new(std::env::current_exe().unwrap())
+            .args(["secrets::secret_service::tests::exercise_store", "--exact", "--ignored"])
+            .env("DBUS_SESSION_BUS_ADDRESS", &address)
+            .env(TEST_BUS_ENV, &address)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(items.lock().unwrap().len(), 1);
+    }
+
+    /// The child half of `test_secret_service_store`
+    #[test]
+    #[ignore = "run by test_secret_service_store"]
+    fn exercise_store() {
+        // Never touch the keyring of the session running the tests
+        let Some(address) = std::env::var_os(TEST_BUS_ENV) else {
+            return;
+        };
+        assert_eq!(std::env::var_os("DBUS_SESSION_BUS_ADDRESS"), Some(address));
+
+        let store = SecretServiceStore;
+        let credentials = |key: &str| Credentials {
+            api_key: key.to_string(),
+            api_secret: "secret".to_string(),
+        };
+        store.store("godaddy", None, &credentials("old")).unwrap();
+        store.store("godaddy", None, &credentials("default")).unwrap();
+        store.store("godaddy", Some("client-a"), &credentials("a")).unwrap();
+
+        assert_eq!(store.list().unwrap().len(), 2);
+        assert_eq!(store.get("godaddy", None).unwrap().unwrap().api_key, "default");
+        assert_eq!(store.get("godaddy", Some("client-a")).unwrap().unwrap().api_key, "a");
+        assert!(store.get("godaddy", Some("client-b")).unwrap().is_none());
+        assert_eq!(
+            store.list().unwrap(),
+            [
+                ("godaddy".to_string(), None),
+                ("godaddy".to_string(), Some("client-a".to_string())),
+            ]
+        );
+
+        assert!(store.delete("godaddy", Some("client-a")).unwrap());
+        assert!(!store.delete("godaddy", Some("client-a")).unwrap());
+        assert_eq!(store.list().unwrap().len(), 1);
+    }
+}